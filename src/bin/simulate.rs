@@ -0,0 +1,195 @@
+//! Runs an auction scenario against the contract in cw-multi-test and prints a timeline of every
+//! step with its events or error, followed by the final auction status and balances.
+//!
+//! ```sh
+//! cargo run --bin simulate --features simulate -- scenarios/example.json
+//! ```
+//!
+//! A scenario is a JSON file naming the seller, the instantiate message, the actors with their
+//! starting balances and the steps to run. `at` is the number of seconds since the auction was
+//! instantiated, steps have to be listed in order.
+
+use std::{env, fs};
+
+use anyhow::{bail, Context, Result};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Event};
+use cw_academy_auction::contract::{execute, instantiate, migrate, query, reply};
+use cw_academy_auction::msg::{AuctionStatusResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use cw_multi_test::{App, ContractWrapper, Executor};
+
+// seconds per block used to derive block heights from step times
+const BLOCK_TIME: u64 = 5;
+
+#[cw_serde]
+struct Actor {
+    name: String,
+    #[serde(default)]
+    balance: Vec<Coin>,
+}
+
+#[cw_serde]
+struct Step {
+    at: u64,
+    sender: String,
+    msg: ExecuteMsg,
+    #[serde(default)]
+    funds: Vec<Coin>,
+}
+
+#[cw_serde]
+struct Scenario {
+    /// Actor instantiating the auction
+    owner: String,
+    instantiate: InstantiateMsg,
+    #[serde(default)]
+    actors: Vec<Actor>,
+    steps: Vec<Step>,
+}
+
+fn format_coins(coins: &[Coin]) -> String {
+    if coins.is_empty() {
+        return "nothing".to_string();
+    }
+
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn format_event(event: &Event) -> String {
+    let attributes = event
+        .attributes
+        .iter()
+        .filter(|attr| attr.key != "_contract_addr")
+        .map(|attr| format!("{}={}", attr.key, attr.value))
+        .collect::<Vec<String>>()
+        .join(" ");
+    format!("{} {}", event.ty, attributes)
+}
+
+// the first key of the serialized message, e.g. `bid` or `close_bidding`
+fn msg_name(msg: &ExecuteMsg) -> Result<String> {
+    let msg = serde_json::to_value(msg)?;
+    Ok(msg
+        .as_object()
+        .and_then(|msg| msg.keys().next().cloned())
+        .unwrap_or_default())
+}
+
+fn run(scenario: Scenario) -> Result<()> {
+    let actors = scenario.actors;
+    let mut app = App::new(|router, _api, storage| {
+        for actor in &actors {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(&actor.name),
+                    actor.balance.clone(),
+                )
+                .unwrap();
+        }
+    });
+
+    let code_id = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query)
+            .with_reply(reply)
+            .with_migrate(migrate),
+    ));
+    let owner = Addr::unchecked(&scenario.owner);
+    let contract = app
+        .instantiate_contract(
+            code_id,
+            owner.clone(),
+            &scenario.instantiate,
+            &[],
+            "Simulated auction",
+            None,
+        )
+        .context("instantiating the auction")?;
+
+    let start = app.block_info();
+    println!("auction {} instantiated by {}", contract, owner);
+
+    let mut last_at = 0;
+    for step in scenario.steps {
+        if step.at < last_at {
+            bail!(
+                "step at {}s is listed after a step at {}s",
+                step.at,
+                last_at
+            );
+        }
+        last_at = step.at;
+
+        app.update_block(|block| {
+            block.time = start.time.plus_seconds(step.at);
+            block.height = start.height + step.at / BLOCK_TIME;
+        });
+
+        println!(
+            "\n[+{}s] {} {} with {}",
+            step.at,
+            step.sender,
+            msg_name(&step.msg)?,
+            format_coins(&step.funds)
+        );
+        match app.execute_contract(
+            Addr::unchecked(&step.sender),
+            contract.clone(),
+            &step.msg,
+            &step.funds,
+        ) {
+            Ok(res) => {
+                for event in res.events.iter().filter(|ev| ev.ty.starts_with("wasm-")) {
+                    println!("  {}", format_event(event));
+                }
+            }
+            Err(err) => println!("  error: {}", err.root_cause()),
+        }
+    }
+
+    let status: AuctionStatusResponse = app
+        .wrap()
+        .query_wasm_smart(&contract, &QueryMsg::GetAuctionStatus {})?;
+    println!("\nfinal status");
+    println!("  active: {}, paused: {}", status.active, status.paused);
+    match status.highest_bid {
+        Some(highest_bid) => println!(
+            "  highest bid: {} by {}",
+            highest_bid.bid, highest_bid.bidder
+        ),
+        None => println!("  highest bid: none"),
+    }
+    println!("  bidders: {}", status.bidders_count);
+
+    println!("\nfinal balances");
+    let mut accounts = vec![scenario.owner];
+    for actor in actors {
+        if !accounts.contains(&actor.name) {
+            accounts.push(actor.name);
+        }
+    }
+    accounts.push(contract.to_string());
+    for account in accounts {
+        let balance = app.wrap().query_all_balances(&account)?;
+        println!("  {}: {}", account, format_coins(&balance));
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => bail!("usage: simulate <scenario.json>"),
+    };
+
+    let scenario = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+    let scenario = serde_json::from_str(&scenario).with_context(|| format!("parsing {}", path))?;
+
+    run(scenario)
+}
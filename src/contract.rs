@@ -1,214 +1,2325 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
-    Uint128,
+    to_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Env, Event, IbcMsg, IbcTimeout,
+    MessageInfo, Order, Reply, Response, StakingMsg, StdError, StdResult, Storage, SubMsg,
+    SubMsgResult, Timestamp, Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{ACTIVE, AUCTION_ITEM_TITLE, BIDS, COMMISSION_PERCENTAGE, OWNER};
+use crate::events::{BidEvent, CloseEvent, RefundEvent, SettleEvent};
+use crate::ics20;
+use crate::msg::{
+    AuctionHookMsg, AuctionPacketMsg, DeliveryEscrow, EscrowStatus, ExecuteMsg,
+    IbcLifecycleComplete, IbcRefundRoute, InstantiateMsg, ItemMetadata, MigrateMsg,
+    OptionalSetting, OracleQueryMsg, PauseScope, PriceResponse, PriceSource, QueryMsg, SudoMsg,
+};
+use crate::state::{
+    PendingIbcRefund, RemoteBidder, StakeWeight, StakingRewards, ACCEPTED_DENOMS, ACTIVE, ARBITER,
+    AUCTION_ITEM_TITLE, AUTO_REFUND_OUTBID, BIDS, BLOCKLIST, COLLECTED_REWARDS,
+    COMMISSION_PERCENTAGE, COMMISSION_RECIPIENT, DELIVERY_TIMEOUT, DEPOSITS, ESCROW,
+    FAILED_REFUNDS, GUARDIAN, HOOKS, IBC_CHANNELS, IBC_COUNTERPARTIES, IBC_REFUNDS_IN_FLIGHT,
+    IBC_REFUNDS_SENDING, IBC_REFUND_ROUTES, ITEM_METADATA, LAST_BID_AT, MAX_BID_PER_BIDDER,
+    MERKLE_ROOT, OWNER, PAUSED, PRICE_SOURCE, REFUND_ADDRESSES, REFUND_CURSOR, REFUND_KEEPER_TIP,
+    REMOTE_BIDDERS, RETRACT_COOLDOWN, SETTLEMENT_PAUSED, STAKE_WEIGHTS, STAKING_CONFIG,
+    STAKING_EXPECTED_BALANCE, STAKING_REWARDS,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:cw-academy-auction";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const BID_DENOM: &str = "ubtc";
+
+const HOOK_REPLY_ID: u64 = 1;
+const STAKING_REPLY_ID: u64 = 2;
+const IBC_REFUND_REPLY_ID: u64 = 3;
+
+// seconds IBC transfers and packets may stay in flight before they time out
+const IBC_TIMEOUT: u64 = 60 * 10;
+
+// seconds the winning bid is escrowed for delivery unless configured otherwise
+const DEFAULT_DELIVERY_TIMEOUT: u64 = 60 * 60 * 24 * 30;
+
+// size limits of the item metadata
+const MAX_TITLE_LENGTH: usize = 140;
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+const MAX_URI_LENGTH: usize = 512;
+const MAX_CATEGORY_LENGTH: usize = 64;
+const MAX_ATTRIBUTES: usize = 32;
+const MAX_ATTRIBUTE_KEY_LENGTH: usize = 64;
+const MAX_ATTRIBUTE_VALUE_LENGTH: usize = 256;
+
+// highest percentage of a refund a keeper can take as their tip
+const MAX_REFUND_KEEPER_TIP_PERCENT: u64 = 5;
+
+// pagination defaults for list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// The leading bidder and their bid, unset while nobody has bid.
+pub fn calc_highest_bid(deps: Deps) -> StdResult<Option<(Addr, Coin)>> {
+    BIDS.range(deps.storage, None, None, Order::Ascending)
+        .try_fold(None, |highest: Option<(Addr, Coin)>, bid| {
+            let (bidder, bid) = bid?;
+            Ok(match highest {
+                Some(highest) if highest.1.amount > bid.amount => Some(highest),
+                _ => Some((bidder, bid)),
+            })
+        })
+}
+
+/// Value of `coin` in the bid denom, using the configured price source for other accepted denoms.
+pub fn normalize_bid(deps: Deps, coin: &Coin) -> Result<Uint128, ContractError> {
+    if coin.denom == BID_DENOM {
+        return Ok(coin.amount);
+    }
+
+    let unsupported = || ContractError::UnsupportedDenom {
+        denom: coin.denom.clone(),
+    };
+
+    let accepted_denoms = ACCEPTED_DENOMS.may_load(deps.storage)?.unwrap_or_default();
+    if !accepted_denoms.contains(&coin.denom) {
+        return Err(unsupported());
+    }
+
+    let rate = match PRICE_SOURCE.load(deps.storage)? {
+        PriceSource::Fixed { rates } => rates
+            .into_iter()
+            .find(|rate| rate.denom == coin.denom)
+            .map(|rate| rate.rate)
+            .ok_or_else(unsupported)?,
+        PriceSource::Oracle { contract } => {
+            let price: PriceResponse = deps.querier.query_wasm_smart(
+                contract,
+                &OracleQueryMsg::Price {
+                    denom: coin.denom.clone(),
+                },
+            )?;
+            price.rate
+        }
+    };
+
+    Ok(coin.amount * rate)
+}
+
+/// Adds `coin` to `coins`, merging it with an existing coin of the same denom.
+pub fn add_coin(coins: &mut Vec<Coin>, coin: Coin) {
+    if coin.amount.is_zero() {
+        return;
+    }
+
+    match coins
+        .iter_mut()
+        .find(|existing| existing.denom == coin.denom)
+    {
+        Some(existing) => existing.amount += coin.amount,
+        None => coins.push(coin),
+    }
+}
+
+pub fn add_deposits(storage: &mut dyn Storage, bidder: &Addr, coins: &[Coin]) -> StdResult<()> {
+    for coin in coins.iter().filter(|coin| !coin.amount.is_zero()) {
+        DEPOSITS.update(storage, (bidder, &coin.denom), |deposit| -> StdResult<_> {
+            Ok(deposit.unwrap_or_default() + coin.amount)
+        })?;
+    }
+
+    Ok(())
+}
+
+pub fn deposits_of(storage: &dyn Storage, bidder: &Addr) -> StdResult<Vec<Coin>> {
+    DEPOSITS
+        .prefix(bidder)
+        .range(storage, None, None, Order::Ascending)
+        .map(|deposit| {
+            let (denom, amount) = deposit?;
+            Ok(Coin { denom, amount })
+        })
+        .collect()
+}
+
+/// Removes and returns every coin the bidder has deposited.
+pub fn take_deposits(storage: &mut dyn Storage, bidder: &Addr) -> StdResult<Vec<Coin>> {
+    let deposits = deposits_of(storage, bidder)?;
+    for deposit in &deposits {
+        DEPOSITS.remove(storage, (bidder, &deposit.denom));
+    }
+
+    Ok(deposits)
+}
+
+/// Where refunds for `bidder` go: the refund address recorded with their bid, or the bidder itself.
+pub fn refund_address_of(storage: &dyn Storage, bidder: &Addr) -> StdResult<Addr> {
+    Ok(REFUND_ADDRESSES
+        .may_load(storage, bidder)?
+        .unwrap_or_else(|| bidder.clone()))
+}
+
+/// Messages paying `coins` back to `bidder`, over IBC when their bid arrived through a transfer memo.
+/// IBC refunds are tracked until their transfer completes and credited back if it fails.
+pub fn refund_msgs(
+    storage: &mut dyn Storage,
+    env: &Env,
+    bidder: &Addr,
+    coins: Vec<Coin>,
+) -> StdResult<Vec<SubMsg>> {
+    if coins.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let route = match IBC_REFUND_ROUTES.may_load(storage, bidder)? {
+        Some(route) => route,
+        None => {
+            return Ok(vec![SubMsg::new(BankMsg::Send {
+                to_address: refund_address_of(storage, bidder)?.to_string(),
+                amount: coins,
+            })])
+        }
+    };
+
+    let mut msgs = vec![];
+    for amount in coins {
+        msgs.push(SubMsg::reply_always(
+            ics20::transfer_msg(
+                &route.channel_id,
+                env.contract.address.as_str(),
+                &route.receiver,
+                &amount,
+                env.block.time.plus_seconds(IBC_TIMEOUT),
+            ),
+            IBC_REFUND_REPLY_ID,
+        ));
+        IBC_REFUNDS_SENDING.push_back(
+            storage,
+            &PendingIbcRefund {
+                bidder: bidder.clone(),
+                channel_id: route.channel_id.clone(),
+                amount,
+            },
+        )?;
+    }
+
+    Ok(msgs)
+}
+
+/// Adds a refund whose transfer failed to what `bidder` can retract.
+fn credit_failed_refund(storage: &mut dyn Storage, refund: PendingIbcRefund) -> StdResult<()> {
+    FAILED_REFUNDS.update(storage, &refund.bidder, |failed| -> StdResult<_> {
+        let mut failed = failed.unwrap_or_default();
+        add_coin(&mut failed, refund.amount);
+        Ok(failed)
+    })?;
+
+    Ok(())
+}
+
+/// Key under which bids of `bidder` on the counterparty of `channel_id` are stored. It is not an
+/// address of this chain, so funds are never sent to it, and it can't collide with one since
+/// addresses never contain a `/`.
+pub fn remote_bid_key(channel_id: &str, bidder: &str) -> Addr {
+    Addr::unchecked(format!("{}/{}", channel_id, bidder))
+}
+
+/// Bid key of `bidder` given to a query: the key of a remote bidder, written as
+/// `<channel_id>/<bidder>` for a channel to a companion contract, or a validated address.
+pub fn bidder_key(deps: Deps, bidder: &str) -> StdResult<Addr> {
+    if let Some((channel_id, remote_bidder)) = bidder.split_once('/') {
+        if IBC_CHANNELS.has(deps.storage, channel_id.to_string()) {
+            return Ok(remote_bid_key(channel_id, remote_bidder));
+        }
+    }
+
+    deps.api.addr_validate(bidder)
+}
+
+/// Sends `packet` to the companion contract on the other end of `channel_id`.
+pub fn auction_packet(env: &Env, channel_id: &str, packet: &AuctionPacketMsg) -> StdResult<IbcMsg> {
+    Ok(IbcMsg::SendPacket {
+        channel_id: channel_id.to_string(),
+        data: to_binary(packet)?,
+        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(IBC_TIMEOUT)),
+    })
+}
+
+/// Builds a submessage sending `hook` to every registered hook contract.
+pub fn prepare_hooks(storage: &dyn Storage, hook: AuctionHookMsg) -> StdResult<Vec<SubMsg>> {
+    HOOKS
+        .keys(storage, None, None, Order::Ascending)
+        .map(|addr| hook.clone().into_sub_msg(addr?, HOOK_REPLY_ID))
+        .collect()
+}
+
+/// Fails unless bids are currently being accepted.
+pub fn ensure_bidding_open(storage: &dyn Storage) -> Result<(), ContractError> {
+    if !ACTIVE.load(storage)? {
+        return Err(ContractError::AuctionInactive {});
+    }
+
+    if PAUSED.load(storage)? {
+        return Err(ContractError::AuctionPaused {});
+    }
+
+    Ok(())
+}
+
+/// Fails while the guardian holds closing and escrow settlement.
+pub fn ensure_settlement_open(storage: &dyn Storage) -> Result<(), ContractError> {
+    if SETTLEMENT_PAUSED.load(storage)? {
+        return Err(ContractError::SettlementPaused {});
+    }
+
+    Ok(())
+}
+
+/// Fails if `address` is blocked from bidding.
+pub fn ensure_not_blocklisted(storage: &dyn Storage, address: &str) -> Result<(), ContractError> {
+    if BLOCKLIST.has(storage, &Addr::unchecked(address)) {
+        return Err(ContractError::Blocklisted {
+            address: address.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Value of all `funds` in the bid denom. Every coin sent is converted, the exact coins are kept
+/// for refunds.
+pub fn bid_value(deps: Deps, funds: &[Coin]) -> Result<Uint128, ContractError> {
+    funds.iter().try_fold(Uint128::zero(), |value, coin| {
+        Ok(value + normalize_bid(deps, coin)?)
+    })
+}
+
+/// Bid that adding some funds would result in
+pub struct CheckedBid {
+    /// Value of the funds in the bid denom
+    pub increment: Coin,
+    pub previous_bid: Coin,
+    pub new_bid: Coin,
+    /// Leading bid before adding the funds
+    pub highest: Option<(Addr, Coin)>,
+}
+
+/// Checks that adding `funds` to the bid of `bidder` takes the lead without exceeding the per
+/// bidder cap. Shared by bidding and `SimulateBid`.
+pub fn check_bid(deps: Deps, bidder: &Addr, funds: &[Coin]) -> Result<CheckedBid, ContractError> {
+    let zero_coin = Coin::new(0, BID_DENOM);
+
+    let new_bid_funds = Coin::new(bid_value(deps, funds)?.u128(), BID_DENOM);
+
+    if new_bid_funds.amount.le(&Uint128::zero()) {
+        return Err(ContractError::InvalidBidAmount {});
+    }
+
+    let highest = calc_highest_bid(deps)?;
+    let highest_amount = highest
+        .as_ref()
+        .map(|(_, highest_bid)| highest_bid.amount)
+        .unwrap_or_default();
+
+    let previous_bid = BIDS.may_load(deps.storage, bidder)?.unwrap_or(zero_coin);
+
+    let new_bid = Coin {
+        denom: BID_DENOM.to_string(),
+        amount: previous_bid.amount + new_bid_funds.amount,
+    };
+
+    if highest_amount >= new_bid.amount {
+        return Err(ContractError::BidTooLow {
+            minimum_bid_amount: highest_amount.u128(),
+            bid_denom: BID_DENOM.to_string(),
+            current_bid_amount: previous_bid.amount.u128(),
+        });
+    }
+
+    if let Some(max_bid) = MAX_BID_PER_BIDDER.may_load(deps.storage)? {
+        if new_bid.amount > max_bid {
+            return Err(ContractError::BidCapExceeded {
+                max_bid_amount: max_bid.u128(),
+                bid_denom: BID_DENOM.to_string(),
+            });
+        }
+    }
+
+    Ok(CheckedBid {
+        increment: new_bid_funds,
+        previous_bid,
+        new_bid,
+        highest,
+    })
+}
+
+/// Adds `funds` to the bid of `bidder` and returns the response for it with the new bid.
+/// Funds of `remote` bidders are escrowed on their own chain and never deposited here.
+pub fn record_bid(
+    deps: DepsMut,
+    env: &Env,
+    bidder: &Addr,
+    funds: &[Coin],
+    remote: Option<RemoteBidder>,
+) -> Result<(Response, Coin), ContractError> {
+    let CheckedBid {
+        increment: new_bid_funds,
+        previous_bid,
+        new_bid,
+        highest,
+    } = check_bid(deps.as_ref(), bidder, funds)?;
+
+    let staking = STAKING_CONFIG.may_load(deps.storage)?;
+    if remote.is_some() && staking.is_some() {
+        return Err(ContractError::RemoteBidWithStaking {});
+    }
+
+    BIDS.save(deps.storage, bidder, &new_bid)?;
+    match &remote {
+        Some(remote) => REMOTE_BIDDERS.save(deps.storage, bidder, remote)?,
+        None => add_deposits(deps.storage, bidder, funds)?,
+    }
+    LAST_BID_AT.save(deps.storage, bidder, &env.block.time)?;
+
+    let mut response = Response::new().add_event(Event::from(BidEvent {
+        auction_id: env.contract.address.clone(),
+        bidder: bidder.to_string(),
+        increment: new_bid_funds.clone(),
+        new_total: new_bid.clone(),
+        previous_leader: highest.as_ref().map(|(leader, _)| leader.to_string()),
+    }));
+
+    let outbid = highest.filter(|(highest_bid_address, _)| highest_bid_address != bidder);
+    if let Some((highest_bid_address, highest_bid)) = &outbid {
+        if let Some(remote_leader) = REMOTE_BIDDERS.may_load(deps.storage, highest_bid_address)? {
+            // remote funds can't be held back here, the companion contract releases them instead
+            BIDS.remove(deps.storage, highest_bid_address);
+            response = response
+                .add_message(auction_packet(
+                    env,
+                    &remote_leader.channel_id,
+                    &AuctionPacketMsg::Outbid {
+                        bidder: remote_leader.bidder,
+                        bid: highest_bid.clone(),
+                        outbid_by: bidder.to_string(),
+                    },
+                )?)
+                .add_event(Event::from(RefundEvent {
+                    auction_id: env.contract.address.clone(),
+                    bidder: highest_bid_address.to_string(),
+                    amount: vec![highest_bid.clone()],
+                }))
+                .add_attribute("refunded", highest_bid_address.to_string())
+                .add_attribute("refund_amount", highest_bid.to_string());
+        } else if AUTO_REFUND_OUTBID.load(deps.storage)? {
+            // only the leading bid stays in escrow, the previous leader gets their funds back right away
+            BIDS.remove(deps.storage, highest_bid_address);
+            let refund = take_deposits(deps.storage, highest_bid_address)?;
+
+            if !refund.is_empty() {
+                response = response
+                    .add_event(Event::from(RefundEvent {
+                        auction_id: env.contract.address.clone(),
+                        bidder: highest_bid_address.to_string(),
+                        amount: refund.clone(),
+                    }))
+                    .add_submessages(refund_msgs(deps.storage, env, highest_bid_address, refund)?)
+                    .add_attribute("refunded", highest_bid_address.to_string())
+                    .add_attribute("refund_amount", highest_bid.to_string());
+            }
+        }
+    }
+
+    if let Some(staking) = staking {
+        accrue_stake_weight(deps.storage, bidder, previous_bid.amount, env.block.time)?;
+
+        // delegating pays out the rewards pending so far, the reply counts them
+        let balance = deps
+            .querier
+            .query_balance(&env.contract.address, BID_DENOM)?
+            .amount;
+        STAKING_EXPECTED_BALANCE.save(
+            deps.storage,
+            &balance
+                .checked_sub(new_bid_funds.amount)
+                .map_err(StdError::from)?,
+        )?;
+
+        response = response.add_submessage(SubMsg::reply_on_success(
+            StakingMsg::Delegate {
+                validator: staking.validator,
+                amount: new_bid_funds,
+            },
+            STAKING_REPLY_ID,
+        ));
+    }
+
+    response = response.add_submessages(prepare_hooks(
+        deps.storage,
+        AuctionHookMsg::NewBid {
+            bidder: bidder.to_string(),
+            bid: new_bid.clone(),
+        },
+    )?);
+
+    if let Some((highest_bid_address, highest_bid)) = outbid {
+        response = response.add_submessages(prepare_hooks(
+            deps.storage,
+            AuctionHookMsg::Outbid {
+                bidder: highest_bid_address.to_string(),
+                bid: highest_bid,
+                outbid_by: bidder.to_string(),
+            },
+        )?);
+    }
+
+    Ok((response, new_bid))
+}
+
+fn check_length(field: &str, value: &str, max: usize) -> Result<(), ContractError> {
+    if value.len() > max {
+        return Err(ContractError::ItemMetadataTooLarge {
+            field: field.to_string(),
+            max,
+        });
+    }
+
+    Ok(())
+}
+
+/// Fails if the commission would take more than the whole payout, or the keeper tip more than
+/// `MAX_REFUND_KEEPER_TIP_PERCENT` of a refund.
+pub fn validate_fees(
+    commission_percentage: Decimal,
+    refund_keeper_tip: Decimal,
+) -> Result<(), ContractError> {
+    if commission_percentage > Decimal::one() {
+        return Err(ContractError::InvalidCommission {});
+    }
+
+    let max = Decimal::percent(MAX_REFUND_KEEPER_TIP_PERCENT);
+    if refund_keeper_tip > max {
+        return Err(ContractError::RefundKeeperTipTooHigh { max });
+    }
+
+    Ok(())
+}
+
+/// Checks the item title and metadata against the size limits.
+pub fn validate_item(title: &str, item: &ItemMetadata) -> Result<(), ContractError> {
+    check_length("title", title, MAX_TITLE_LENGTH)?;
+
+    let optional_fields = [
+        ("description", &item.description, MAX_DESCRIPTION_LENGTH),
+        ("uri", &item.uri, MAX_URI_LENGTH),
+        ("category", &item.category, MAX_CATEGORY_LENGTH),
+    ];
+    for (field, value, max) in optional_fields {
+        if let Some(value) = value {
+            check_length(field, value, max)?;
+        }
+    }
+
+    if item.attributes.len() > MAX_ATTRIBUTES {
+        return Err(ContractError::ItemMetadataTooLarge {
+            field: "attributes".to_string(),
+            max: MAX_ATTRIBUTES,
+        });
+    }
+    for attribute in &item.attributes {
+        check_length("attribute key", &attribute.key, MAX_ATTRIBUTE_KEY_LENGTH)?;
+        check_length(
+            "attribute value",
+            &attribute.value,
+            MAX_ATTRIBUTE_VALUE_LENGTH,
+        )?;
+    }
+
+    if let Some(content_hash) = &item.content_hash {
+        decode_hash(content_hash)?;
+    }
+
+    Ok(())
+}
+
+/// Whether anyone has placed a bid since the auction was created.
+pub fn bidding_started(storage: &dyn Storage) -> bool {
+    LAST_BID_AT
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+}
+
+/// Whether anyone other than `owner` holds a bid, including bids placed before the last
+/// migration.
+pub fn third_party_bids_exist(storage: &dyn Storage, owner: &Addr) -> StdResult<bool> {
+    for bidder in BIDS.keys(storage, None, None, Order::Ascending) {
+        if &bidder? != owner {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Decodes a hex encoded sha256 hash, failing if it is not exactly 32 bytes long.
+fn decode_hash(hash: &str) -> Result<[u8; 32], ContractError> {
+    let mut buf = [0; 32];
+    hex::decode_to_slice(hash, &mut buf)?;
+    Ok(buf)
+}
+
+/// Checks that the bidder is part of the allowlist merkle tree, if one is configured.
+/// Leaves are the sha256 of the bidder address and each pair is sorted before hashing.
+pub fn verify_allowlist(
+    deps: Deps,
+    bidder: &str,
+    proof: Option<Vec<String>>,
+) -> Result<(), ContractError> {
+    let merkle_root = match MERKLE_ROOT.may_load(deps.storage)? {
+        Some(merkle_root) => decode_hash(&merkle_root)?,
+        None => return Ok(()),
+    };
+
+    let leaf: [u8; 32] = Sha256::digest(bidder.as_bytes()).into();
+    let computed_root = proof
+        .unwrap_or_default()
+        .iter()
+        .try_fold(leaf, |hash, proof_hash| {
+            let mut pair = [hash, decode_hash(proof_hash)?];
+            pair.sort_unstable();
+            Ok::<[u8; 32], ContractError>(Sha256::digest(pair.concat()).into())
+        })?;
+
+    if computed_root != merkle_root {
+        return Err(ContractError::NotAllowlisted {});
+    }
+
+    Ok(())
+}
+
+/// Adds the time weighted amount a bidder had staked since their last update.
+/// `amount` is the bid the bidder held over that period.
+pub fn accrue_stake_weight(
+    storage: &mut dyn Storage,
+    bidder: &Addr,
+    amount: Uint128,
+    now: Timestamp,
+) -> StdResult<()> {
+    let stake_weight = match STAKE_WEIGHTS.may_load(storage, bidder)? {
+        Some(stake_weight) => StakeWeight {
+            weight: final_stake_weight(&stake_weight, amount, now)?,
+            last_updated: now,
+        },
+        None => StakeWeight {
+            weight: Uint128::zero(),
+            last_updated: now,
+        },
+    };
+
+    STAKE_WEIGHTS.save(storage, bidder, &stake_weight)
+}
+
+/// Stake weight of a bidder up to `until`, assuming their bid has not changed since the last update.
+pub fn final_stake_weight(
+    stake_weight: &StakeWeight,
+    amount: Uint128,
+    until: Timestamp,
+) -> StdResult<Uint128> {
+    let elapsed = until
+        .seconds()
+        .saturating_sub(stake_weight.last_updated.seconds());
+    Ok(stake_weight
+        .weight
+        .checked_add(amount.checked_mul(Uint128::from(elapsed))?)?)
+}
+
+/// The bidder's pro rata share of the staking rewards. Zero once claimed.
+pub fn pending_staking_reward(
+    deps: Deps,
+    staking_rewards: &StakingRewards,
+    bidder: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    if staking_rewards.total_weight.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    Ok(match STAKE_WEIGHTS.may_load(deps.storage, bidder)? {
+        Some(stake_weight) => staking_rewards.total_rewards.multiply_ratio(
+            final_stake_weight(&stake_weight, amount, staking_rewards.closed_at)?,
+            staking_rewards.total_weight,
+        ),
+        None => Uint128::zero(),
+    })
+}
+
+/// Staked `funds` scaled down to what came back from the validator after a slash.
+pub fn unstaked_funds(staking_rewards: Option<&StakingRewards>, funds: Vec<Coin>) -> Vec<Coin> {
+    let staking_rewards = match staking_rewards {
+        Some(staking_rewards) if !staking_rewards.total_staked.is_zero() => staking_rewards,
+        _ => return funds,
+    };
+
+    let mut unstaked = vec![];
+    for coin in funds {
+        let amount = coin
+            .amount
+            .multiply_ratio(staking_rewards.undelegated, staking_rewards.total_staked);
+        add_coin(&mut unstaked, Coin::new(amount.u128(), coin.denom));
+    }
+    unstaked
+}
+
+/// Whether the stake undelegated at close is back. The unbonding queue can't be queried, so the
+/// stake counts as back once the liquid balance grew by the undelegated amount.
+pub fn staking_unbonded(
+    deps: Deps,
+    env: &Env,
+    staking_rewards: &StakingRewards,
+) -> StdResult<bool> {
+    if staking_rewards.unbonded {
+        return Ok(true);
+    }
+
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, BID_DENOM)?
+        .amount;
+    Ok(balance >= staking_rewards.balance_at_close + staking_rewards.undelegated)
+}
+
+/// Fails while the stake is unbonding. Payouts lower the balance, so the first one records that
+/// unbonding is over.
+pub fn ensure_unbonded(deps: DepsMut, env: &Env) -> Result<Option<StakingRewards>, ContractError> {
+    let mut staking_rewards = match STAKING_REWARDS.may_load(deps.storage)? {
+        Some(staking_rewards) => staking_rewards,
+        None => return Ok(None),
+    };
+
+    if !staking_rewards.unbonded {
+        if !staking_unbonded(deps.as_ref(), env, &staking_rewards)? {
+            return Err(ContractError::FundsUnbonding {});
+        }
+
+        staking_rewards.unbonded = true;
+        STAKING_REWARDS.save(deps.storage, &staking_rewards)?;
+    }
+
+    Ok(Some(staking_rewards))
+}
+
+/// Bid of `bidder` and how much of it `RetractFunds` could pay out right now, failing while
+/// withdrawals are blocked. Shared by retracting and `GetBidderStatus`.
+pub fn withdrawable_bid(
+    deps: Deps,
+    env: &Env,
+    bidder: &Addr,
+) -> Result<(Coin, Uint128), ContractError> {
+    let auction_active = ACTIVE.load(deps.storage)?;
+    if auction_active {
+        // delegated bids can only be released once bidding closes
+        if STAKING_CONFIG.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::AuctionActive {});
+        }
+
+        if let (Some(cooldown), Some(last_bid_at)) = (
+            RETRACT_COOLDOWN.may_load(deps.storage)?,
+            LAST_BID_AT.may_load(deps.storage, bidder)?,
+        ) {
+            let available_at = last_bid_at.plus_seconds(cooldown);
+            if env.block.time < available_at {
+                return Err(ContractError::RetractCooldown { available_at });
+            }
+        }
+    }
+
+    if let Some(staking_rewards) = STAKING_REWARDS.may_load(deps.storage)? {
+        if !staking_unbonded(deps, env, &staking_rewards)? {
+            return Err(ContractError::FundsUnbonding {});
+        }
+    }
+
+    let highest_bidder_address = calc_highest_bid(deps)?.map(|(bidder, _)| bidder);
+
+    let bid = BIDS
+        .may_load(deps.storage, bidder)?
+        .unwrap_or(Coin::new(0, BID_DENOM));
+
+    // the leader keeps their bid in escrow but can still claim their staking rewards
+    let available = if highest_bidder_address.as_ref() == Some(bidder) {
+        Uint128::zero()
+    } else {
+        bid.amount
+    };
+
+    Ok((bid, available))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // the seller doesn't bid on their own item, auctions start without any bids
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedFunds {});
+    }
+
+    let owner_addr = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender.clone(),
+    };
+    OWNER.save(deps.storage, &owner_addr)?;
+
+    let guardian_addr = match msg.guardian {
+        Some(guardian) => deps.api.addr_validate(&guardian)?,
+        None => owner_addr.clone(),
+    };
+    GUARDIAN.save(deps.storage, &guardian_addr)?;
+
+    if let Some(arbiter) = msg.arbiter {
+        let arbiter = deps.api.addr_validate(&arbiter)?;
+        ARBITER.save(deps.storage, &arbiter)?;
+    }
+    DELIVERY_TIMEOUT.save(
+        deps.storage,
+        &msg.delivery_timeout.unwrap_or(DEFAULT_DELIVERY_TIMEOUT),
+    )?;
+
+    let commission = msg
+        .commission_percentage
+        .unwrap_or(Decimal::new(Uint128::new(50_000_000_000_000_000)));
+    COMMISSION_PERCENTAGE.save(deps.storage, &commission)?;
+    let commission_recipient = match msg.commission_recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender.clone(),
+    };
+    COMMISSION_RECIPIENT.save(deps.storage, &commission_recipient)?;
+
+    let item = msg.item.unwrap_or_default();
+    validate_item(&msg.auction_item_title, &item)?;
+    AUCTION_ITEM_TITLE.save(deps.storage, &msg.auction_item_title)?;
+    ITEM_METADATA.save(deps.storage, &item)?;
+    ACTIVE.save(deps.storage, &true)?;
+    PAUSED.save(deps.storage, &false)?;
+    SETTLEMENT_PAUSED.save(deps.storage, &false)?;
+
+    if let Some(merkle_root) = msg.merkle_root {
+        decode_hash(&merkle_root)?;
+        MERKLE_ROOT.save(deps.storage, &merkle_root)?;
+    }
+
+    if let Some(max_bid_per_bidder) = msg.max_bid_per_bidder {
+        MAX_BID_PER_BIDDER.save(deps.storage, &max_bid_per_bidder)?;
+    }
+
+    let accepted_denoms = msg.accepted_denoms.unwrap_or_default();
+    if !accepted_denoms.is_empty() {
+        match &msg.price_source {
+            Some(PriceSource::Oracle { contract }) => {
+                deps.api.addr_validate(contract)?;
+            }
+            Some(PriceSource::Fixed { .. }) => {}
+            None => return Err(ContractError::MissingPriceSource {}),
+        }
+
+        if msg.staking.is_some() {
+            return Err(ContractError::StakingRequiresSingleDenom {});
+        }
+    }
+    ACCEPTED_DENOMS.save(deps.storage, &accepted_denoms)?;
+
+    if let Some(price_source) = msg.price_source {
+        PRICE_SOURCE.save(deps.storage, &price_source)?;
+    }
+
+    let auto_refund_outbid = msg.auto_refund_outbid.unwrap_or(false);
+    if auto_refund_outbid && msg.staking.is_some() {
+        return Err(ContractError::AutoRefundWithStaking {});
+    }
+    AUTO_REFUND_OUTBID.save(deps.storage, &auto_refund_outbid)?;
+
+    if let Some(retract_cooldown) = msg.retract_cooldown {
+        RETRACT_COOLDOWN.save(deps.storage, &retract_cooldown)?;
+    }
+
+    let refund_keeper_tip = msg.refund_keeper_tip.unwrap_or_else(Decimal::zero);
+    validate_fees(commission, refund_keeper_tip)?;
+    REFUND_KEEPER_TIP.save(deps.storage, &refund_keeper_tip)?;
+
+    if let Some(staking) = msg.staking {
+        let bonded_denom = deps.querier.query_bonded_denom()?;
+        if bonded_denom != BID_DENOM {
+            return Err(ContractError::InvalidStakingDenom { bonded_denom });
+        }
+
+        if deps.querier.query_validator(&staking.validator)?.is_none() {
+            return Err(ContractError::UnknownValidator {
+                validator: staking.validator,
+            });
+        }
+
+        STAKING_CONFIG.save(deps.storage, &staking)?;
+        COLLECTED_REWARDS.save(deps.storage, &Uint128::zero())?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute("sender", info.sender))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Bid {
+            bidder,
+            refund_address,
+            ibc_refund,
+            proof,
+        } => execute::bid(deps, env, info, bidder, refund_address, ibc_refund, proof),
+        ExecuteMsg::CloseBidding {} => execute::close(deps, env, info),
+        ExecuteMsg::ProcessRefunds { limit } => execute::process_refunds(deps, env, info, limit),
+        ExecuteMsg::RetractFunds {
+            withdraw_address,
+            amount,
+        } => execute::retract(deps, env, info, withdraw_address, amount),
+        ExecuteMsg::UpdateMerkleRoot { merkle_root } => {
+            execute::update_merkle_root(deps, info, merkle_root)
+        }
+        ExecuteMsg::AddToBlocklist { addresses } => {
+            execute::add_to_blocklist(deps, info, addresses)
+        }
+        ExecuteMsg::RemoveFromBlocklist { addresses } => {
+            execute::remove_from_blocklist(deps, info, addresses)
+        }
+        ExecuteMsg::Pause { scope } => execute::set_paused(deps, info, scope, true),
+        ExecuteMsg::Unpause { scope } => execute::set_paused(deps, info, scope, false),
+        ExecuteMsg::UpdateGuardian { guardian } => execute::update_guardian(deps, info, guardian),
+        ExecuteMsg::UpdatePriceSource { price_source } => {
+            execute::update_price_source(deps, info, price_source)
+        }
+        ExecuteMsg::AddHook { addr } => execute::add_hook(deps, info, addr),
+        ExecuteMsg::RemoveHook { addr } => execute::remove_hook(deps, info, addr),
+        ExecuteMsg::UpdateConfig {
+            auction_item_title,
+            commission_percentage,
+            commission_recipient,
+            max_bid_per_bidder,
+            guardian,
+            retract_cooldown,
+            refund_keeper_tip,
+            arbiter,
+            delivery_timeout,
+            clear,
+        } => execute::update_config(
+            deps,
+            info,
+            auction_item_title,
+            commission_percentage,
+            commission_recipient,
+            max_bid_per_bidder,
+            guardian,
+            retract_cooldown,
+            refund_keeper_tip,
+            arbiter,
+            delivery_timeout,
+            clear.unwrap_or_default(),
+        ),
+        ExecuteMsg::UpdateItem { item } => execute::update_item(deps, info, item),
+        ExecuteMsg::ConfirmDelivery {} => execute::confirm_delivery(deps, env, info),
+        ExecuteMsg::ReleaseEscrow {} => execute::release_escrow(deps, env, info),
+        ExecuteMsg::OpenDispute {} => execute::open_dispute(deps, info),
+        ExecuteMsg::ResolveDispute { buyer_share } => {
+            execute::resolve_dispute(deps, env, info, buyer_share)
+        }
+        ExecuteMsg::RetryPayout {} => execute::retry_payout(deps, env, info),
+        ExecuteMsg::AllowIbcCounterparty {
+            connection_id,
+            port_id,
+        } => execute::allow_ibc_counterparty(deps, info, connection_id, port_id),
+        ExecuteMsg::RemoveIbcCounterparty {
+            connection_id,
+            port_id,
+        } => execute::remove_ibc_counterparty(deps, info, connection_id, port_id),
+    }
+}
+
+pub mod execute {
+    use cosmwasm_std::{Coin, DistributionMsg, Empty};
+
+    use cw_storage_plus::Bound;
+
+    use crate::msg::BidResponse;
+
+    use crate::state::BIDS;
+
+    use super::*;
+
+    pub fn bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        bidder: Option<String>,
+        refund_address: Option<String>,
+        ibc_refund: Option<IbcRefundRoute>,
+        proof: Option<Vec<String>>,
+    ) -> Result<Response, ContractError> {
+        ensure_bidding_open(deps.storage)?;
+
+        // custodians can bid for someone else, the bid and any refund then belong to the beneficiary
+        let bidder = match bidder {
+            Some(bidder) => deps.api.addr_validate(&bidder)?,
+            None => info.sender.clone(),
+        };
+
+        verify_allowlist(deps.as_ref(), bidder.as_str(), proof)?;
+
+        for address in [&info.sender, &bidder] {
+            ensure_not_blocklisted(deps.storage, address.as_str())?;
+        }
+
+        if let Some(refund_address) = refund_address {
+            let refund_address = deps.api.addr_validate(&refund_address)?;
+
+            // anyone could redirect someone else's refunds with a dust bid otherwise. A custodian
+            // only sets it with the first funds of the bidder, and can only repeat it afterwards.
+            if info.sender != bidder {
+                let recorded = REFUND_ADDRESSES.may_load(deps.storage, &bidder)?;
+                let unchanged = recorded.as_ref() == Some(&refund_address);
+                let first_funds =
+                    recorded.is_none() && deposits_of(deps.storage, &bidder)?.is_empty();
+                if !unchanged && !first_funds {
+                    return Err(ContractError::RefundAddressLocked {});
+                }
+            }
+
+            REFUND_ADDRESSES.save(deps.storage, &bidder, &refund_address)?;
+        }
+
+        // bids relayed by an ICS-20 memo hook come from a derived address nobody holds keys for,
+        // so refunds have to travel back over the channel the funds arrived on
+        if let Some(ibc_refund) = ibc_refund {
+            // the route decides where refunds go, a custodian can't set it for someone else
+            if info.sender != bidder {
+                return Err(ContractError::ForeignIbcRefundRoute {});
+            }
+
+            if ibc_refund.channel_id.is_empty() || ibc_refund.receiver.is_empty() {
+                return Err(ContractError::InvalidIbcRefundRoute {});
+            }
+            IBC_REFUND_ROUTES.save(deps.storage, &bidder, &ibc_refund)?;
+        }
+
+        let (response, new_bid) = record_bid(deps, &env, &bidder, &info.funds, None)?;
+
+        Ok(response
+            .add_attribute("action", "bid")
+            .add_attribute("sender", info.sender.to_string())
+            .add_attribute("bidder", bidder.to_string())
+            .add_attribute("bid_amount", new_bid.to_string()))
+    }
+
+    pub fn close(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let auction_active = ACTIVE.load(deps.storage)?;
+        if !auction_active {
+            return Err(ContractError::AuctionInactive {});
+        }
+
+        ensure_settlement_open(deps.storage)?;
+
+        ACTIVE.save(deps.storage, &false)?;
+
+        let highest = calc_highest_bid(deps.as_ref())?;
+        let mut response = Response::new()
+            .add_event(Event::from(CloseEvent {
+                auction_id: env.contract.address.clone(),
+                winner: highest
+                    .clone()
+                    .map(|(bidder, bid)| (bidder.to_string(), bid)),
+            }))
+            .add_submessages(prepare_hooks(
+                deps.storage,
+                AuctionHookMsg::Closed {
+                    highest_bid: highest.clone().map(|(bidder, bid)| BidResponse {
+                        bidder: bidder.to_string(),
+                        bid,
+                    }),
+                },
+            )?);
+
+        let remote_winner = match &highest {
+            Some((highest_bidder, _)) => REMOTE_BIDDERS.may_load(deps.storage, highest_bidder)?,
+            None => None,
+        };
+        for channel_id in IBC_CHANNELS.keys(deps.storage, None, None, Order::Ascending) {
+            let channel_id = channel_id?;
+            let winner = remote_winner
+                .as_ref()
+                .filter(|remote| remote.channel_id == channel_id)
+                .map(|remote| remote.bidder.clone());
+            response = response.add_message(auction_packet(
+                &env,
+                &channel_id,
+                &AuctionPacketMsg::Settled {
+                    winner,
+                    bid: highest.as_ref().map(|(_, highest_bid)| highest_bid.clone()),
+                },
+            )?);
+        }
+
+        let staking_rewards = match STAKING_CONFIG.may_load(deps.storage)? {
+            Some(staking) => {
+                let delegation = deps
+                    .querier
+                    .query_delegation(&env.contract.address, &staking.validator)?;
+
+                let mut total_weight = Uint128::zero();
+                let mut total_staked = Uint128::zero();
+                for stake_weight in STAKE_WEIGHTS.range(deps.storage, None, None, Order::Ascending)
+                {
+                    let (bidder, stake_weight) = stake_weight?;
+                    let bid = BIDS.load(deps.storage, &bidder)?;
+                    total_weight += final_stake_weight(&stake_weight, bid.amount, env.block.time)?;
+                    total_staked += bid.amount;
+                }
+
+                let balance = deps
+                    .querier
+                    .query_balance(&env.contract.address, BID_DENOM)?
+                    .amount;
+                let staking_rewards = StakingRewards {
+                    total_rewards: COLLECTED_REWARDS.load(deps.storage)?,
+                    total_weight,
+                    closed_at: env.block.time,
+                    total_staked,
+                    undelegated: delegation
+                        .as_ref()
+                        .map(|delegation| delegation.amount.amount)
+                        .unwrap_or_default(),
+                    balance_at_close: balance,
+                    unbonded: delegation.is_none(),
+                };
+                STAKING_REWARDS.save(deps.storage, &staking_rewards)?;
+
+                if let Some(delegation) = delegation {
+                    // rewards have to be withdrawn first, they are lost once the whole stake is
+                    // undelegated. The reply adds them to the snapshot.
+                    if delegation
+                        .accumulated_rewards
+                        .iter()
+                        .any(|reward| reward.denom == BID_DENOM && !reward.amount.is_zero())
+                    {
+                        response = response.add_message(DistributionMsg::WithdrawDelegatorReward {
+                            validator: staking.validator.clone(),
+                        });
+                    }
+
+                    STAKING_EXPECTED_BALANCE.save(deps.storage, &balance)?;
+                    response = response.add_submessage(SubMsg::reply_on_success(
+                        StakingMsg::Undelegate {
+                            validator: staking.validator,
+                            amount: delegation.amount,
+                        },
+                        STAKING_REPLY_ID,
+                    ));
+                }
+
+                Some(staking_rewards)
+            }
+            None => None,
+        };
+
+        // the winning funds stay in escrow until the item is delivered. The companion contract
+        // holds the funds of a remote winner and pays them out once told how the escrow settled.
+        if let Some((highest_bidder, highest_bid)) = highest {
+            let winning_funds = unstaked_funds(
+                staking_rewards.as_ref(),
+                take_deposits(deps.storage, &highest_bidder)?,
+            );
+            let remote_channel = remote_winner.map(|remote| remote.channel_id);
+            if !winning_funds.is_empty() || remote_channel.is_some() {
+                let delivery_timeout = DELIVERY_TIMEOUT.load(deps.storage)?;
+                ESCROW.save(
+                    deps.storage,
+                    &DeliveryEscrow {
+                        winner: highest_bidder,
+                        bid: highest_bid,
+                        funds: winning_funds,
+                        release_at: env.block.time.plus_seconds(delivery_timeout),
+                        status: EscrowStatus::AwaitingDelivery,
+                        remote_channel,
+                        buyer_share: None,
+                    },
+                )?;
+            }
+        }
+
+        Ok(response
+            .add_attribute("action", "close_bidding")
+            .add_attribute("sender", info.sender.to_string()))
+    }
+
+    pub fn retract(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        withdraw_address: Option<String>,
+        amount: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let staking_rewards = ensure_unbonded(deps.branch(), &env)?;
+        let (bid, available) = withdrawable_bid(deps.as_ref(), &env, &info.sender)?;
+
+        let refund = match amount {
+            Some(amount) if amount > available => {
+                return Err(ContractError::WithdrawalTooLarge {
+                    available: available.u128(),
+                    bid_denom: BID_DENOM.to_string(),
+                })
+            }
+            Some(amount) => amount,
+            None => available,
+        };
+
+        let staking_reward = match &staking_rewards {
+            Some(staking_rewards) => {
+                pending_staking_reward(deps.as_ref(), staking_rewards, &info.sender, bid.amount)?
+            }
+            None => Uint128::zero(),
+        };
+
+        let failed_refunds = FAILED_REFUNDS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+
+        if refund.is_zero() && staking_reward.is_zero() && failed_refunds.is_empty() {
+            return Err(ContractError::NothingToWithdraw {});
+        }
+
+        let remaining = bid.amount - refund;
+        let withdrawl = if remaining.is_zero() {
+            BIDS.remove(deps.storage, &info.sender);
+            take_deposits(deps.storage, &info.sender)?
+        } else if !refund.is_zero() {
+            // a partial refund can't be split back into the original coins of a mixed bid
+            let deposits = deposits_of(deps.storage, &info.sender)?;
+            if deposits.iter().any(|deposit| deposit.denom != BID_DENOM) {
+                return Err(ContractError::PartialWithdrawalUnsupported {});
+            }
+
+            BIDS.save(
+                deps.storage,
+                &info.sender,
+                &Coin::new(remaining.u128(), BID_DENOM),
+            )?;
+            DEPOSITS.save(deps.storage, (&info.sender, BID_DENOM), &remaining)?;
+            vec![Coin::new(refund.u128(), BID_DENOM)]
+        } else {
+            vec![]
+        };
+        let mut withdrawl = unstaked_funds(staking_rewards.as_ref(), withdrawl);
+        add_coin(&mut withdrawl, Coin::new(staking_reward.u128(), BID_DENOM));
+        for coin in failed_refunds {
+            add_coin(&mut withdrawl, coin);
+        }
+        STAKE_WEIGHTS.remove(deps.storage, &info.sender);
+        FAILED_REFUNDS.remove(deps.storage, &info.sender);
+
+        let refund_event = Event::from(RefundEvent {
+            auction_id: env.contract.address.clone(),
+            bidder: info.sender.to_string(),
+            amount: withdrawl.clone(),
+        });
+        let msgs = match withdraw_address {
+            Some(to_address) => vec![SubMsg::new(BankMsg::Send {
+                to_address: deps.api.addr_validate(&to_address)?.to_string(),
+                amount: withdrawl,
+            })],
+            None => refund_msgs(deps.storage, &env, &info.sender, withdrawl)?,
+        };
+
+        Ok(Response::new()
+            .add_event(refund_event)
+            .add_submessages(msgs)
+            .add_attribute("action", "retract_funds")
+            .add_attribute("sender", info.sender))
+    }
+
+    pub fn process_refunds(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let auction_active = ACTIVE.load(deps.storage)?;
+        if auction_active {
+            return Err(ContractError::AuctionActive {});
+        }
+
+        let staking_rewards = ensure_unbonded(deps.branch(), &env)?;
+
+        let highest_bidder_address = calc_highest_bid(deps.as_ref())?.map(|(bidder, _)| bidder);
+        let keeper_tip = REFUND_KEEPER_TIP.load(deps.storage)?;
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let cursor = REFUND_CURSOR.may_load(deps.storage)?;
+        let start = cursor.as_ref().map(Bound::exclusive);
+        let bids = BIDS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<(Addr, Coin)>>>()?;
+
+        let mut response = Response::new();
+        let mut refunded_count = 0u32;
+        let mut total_tip = vec![];
+
+        for (bidder, bid) in &bids {
+            if highest_bidder_address.as_ref() == Some(bidder) {
+                continue;
+            }
+
+            let staking_reward = match &staking_rewards {
+                Some(staking_rewards) => {
+                    pending_staking_reward(deps.as_ref(), staking_rewards, bidder, bid.amount)?
+                }
+                None => Uint128::zero(),
+            };
+
+            let mut refund = unstaked_funds(
+                staking_rewards.as_ref(),
+                take_deposits(deps.storage, bidder)?,
+            );
+            add_coin(&mut refund, Coin::new(staking_reward.u128(), BID_DENOM));
+            if refund.is_empty() {
+                continue;
+            }
+
+            let mut refund_after_tip = vec![];
+            for coin in refund {
+                let tip = coin.amount * keeper_tip;
+                add_coin(&mut total_tip, Coin::new(tip.u128(), &coin.denom));
+                add_coin(
+                    &mut refund_after_tip,
+                    Coin::new((coin.amount - tip).u128(), coin.denom),
+                );
+            }
+            refunded_count += 1;
+
+            BIDS.remove(deps.storage, bidder);
+            STAKE_WEIGHTS.remove(deps.storage, bidder);
+
+            response = response
+                .add_event(Event::from(RefundEvent {
+                    auction_id: env.contract.address.clone(),
+                    bidder: bidder.to_string(),
+                    amount: refund_after_tip.clone(),
+                }))
+                .add_submessages(refund_msgs(deps.storage, &env, bidder, refund_after_tip)?);
+        }
+
+        if let Some((last_bidder, _)) = bids.last() {
+            REFUND_CURSOR.save(deps.storage, last_bidder)?;
+        }
+
+        let keeper_tip_attr = if total_tip.is_empty() {
+            "0".to_string()
+        } else {
+            total_tip
+                .iter()
+                .map(Coin::to_string)
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        if !total_tip.is_empty() {
+            response = response.add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: total_tip,
+            });
+        }
+
+        Ok(response
+            .add_attribute("action", "process_refunds")
+            .add_attribute("sender", info.sender)
+            .add_attribute("refunded_count", refunded_count.to_string())
+            .add_attribute("keeper_tip", keeper_tip_attr)
+            .add_attribute("complete", (bids.len() < limit).to_string()))
+    }
+
+    pub fn update_merkle_root(
+        deps: DepsMut,
+        info: MessageInfo,
+        merkle_root: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        match &merkle_root {
+            Some(merkle_root) => {
+                decode_hash(merkle_root)?;
+                MERKLE_ROOT.save(deps.storage, merkle_root)?;
+            }
+            None => MERKLE_ROOT.remove(deps.storage),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "update_merkle_root")
+            .add_attribute("sender", info.sender)
+            .add_attribute(
+                "merkle_root",
+                merkle_root.unwrap_or_else(|| "none".to_string()),
+            ))
+    }
+
+    pub fn add_to_blocklist(
+        deps: DepsMut,
+        info: MessageInfo,
+        addresses: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        for address in &addresses {
+            let address = deps.api.addr_validate(address)?;
+            BLOCKLIST.save(deps.storage, &address, &Empty {})?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "add_to_blocklist")
+            .add_attribute("sender", info.sender)
+            .add_attribute("addresses", addresses.join(",")))
+    }
+
+    pub fn remove_from_blocklist(
+        deps: DepsMut,
+        info: MessageInfo,
+        addresses: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        for address in &addresses {
+            let address = deps.api.addr_validate(address)?;
+            BLOCKLIST.remove(deps.storage, &address);
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_from_blocklist")
+            .add_attribute("sender", info.sender)
+            .add_attribute("addresses", addresses.join(",")))
+    }
+
+    pub fn set_paused(
+        deps: DepsMut,
+        info: MessageInfo,
+        scope: Option<PauseScope>,
+        paused: bool,
+    ) -> Result<Response, ContractError> {
+        let guardian = GUARDIAN.load(deps.storage)?;
+        if info.sender != guardian {
+            return Err(ContractError::Unauthorized {
+                owner: guardian.to_string(),
+            });
+        }
+
+        let scope = match scope {
+            Some(PauseScope::Bidding) => {
+                PAUSED.save(deps.storage, &paused)?;
+                "bidding"
+            }
+            Some(PauseScope::Settlement) => {
+                SETTLEMENT_PAUSED.save(deps.storage, &paused)?;
+                "settlement"
+            }
+            None => {
+                PAUSED.save(deps.storage, &paused)?;
+                SETTLEMENT_PAUSED.save(deps.storage, &paused)?;
+                "all"
+            }
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", if paused { "pause" } else { "unpause" })
+            .add_attribute("sender", info.sender)
+            .add_attribute("scope", scope))
+    }
+
+    /// Unlike `UpdateConfig` this works once bidding started, so a lost guardian key can be
+    /// replaced mid-auction.
+    pub fn update_guardian(
+        deps: DepsMut,
+        info: MessageInfo,
+        guardian: String,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let guardian = deps.api.addr_validate(&guardian)?;
+        GUARDIAN.save(deps.storage, &guardian)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_guardian")
+            .add_attribute("sender", info.sender)
+            .add_attribute("guardian", guardian))
+    }
+
+    pub fn update_price_source(
+        deps: DepsMut,
+        info: MessageInfo,
+        price_source: PriceSource,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        if let PriceSource::Oracle { contract } = &price_source {
+            deps.api.addr_validate(contract)?;
+        }
+        PRICE_SOURCE.save(deps.storage, &price_source)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_price_source")
+            .add_attribute("sender", info.sender))
+    }
+
+    pub fn add_hook(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let addr = deps.api.addr_validate(&addr)?;
+        HOOKS.save(deps.storage, &addr, &Empty {})?;
+
+        Ok(Response::new()
+            .add_attribute("action", "add_hook")
+            .add_attribute("sender", info.sender)
+            .add_attribute("hook", addr))
+    }
+
+    pub fn remove_hook(
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: String,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        let addr = deps.api.addr_validate(&addr)?;
+        HOOKS.remove(deps.storage, &addr);
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_hook")
+            .add_attribute("sender", info.sender)
+            .add_attribute("hook", addr))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_config(
+        deps: DepsMut,
+        info: MessageInfo,
+        auction_item_title: Option<String>,
+        commission_percentage: Option<Decimal>,
+        commission_recipient: Option<String>,
+        max_bid_per_bidder: Option<Uint128>,
+        guardian: Option<String>,
+        retract_cooldown: Option<u64>,
+        refund_keeper_tip: Option<Decimal>,
+        arbiter: Option<String>,
+        delivery_timeout: Option<u64>,
+        clear: Vec<OptionalSetting>,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        // the terms can't change under someone who already committed funds
+        if third_party_bids_exist(deps.storage, &owner)? {
+            return Err(ContractError::BiddingStarted {});
+        }
+
+        validate_fees(
+            commission_percentage.unwrap_or(COMMISSION_PERCENTAGE.load(deps.storage)?),
+            refund_keeper_tip.unwrap_or(REFUND_KEEPER_TIP.load(deps.storage)?),
+        )?;
+
+        // every changed field is listed with its new value
+        let mut event = Event::new("update_config");
+
+        if let Some(title) = auction_item_title {
+            validate_item(&title, &ITEM_METADATA.load(deps.storage)?)?;
+            AUCTION_ITEM_TITLE.save(deps.storage, &title)?;
+            event = event.add_attribute("auction_item_title", title);
+        }
+
+        if let Some(commission_percentage) = commission_percentage {
+            COMMISSION_PERCENTAGE.save(deps.storage, &commission_percentage)?;
+            event = event.add_attribute("commission_percentage", commission_percentage.to_string());
+        }
+
+        if let Some(commission_recipient) = commission_recipient {
+            let commission_recipient = deps.api.addr_validate(&commission_recipient)?;
+            COMMISSION_RECIPIENT.save(deps.storage, &commission_recipient)?;
+            event = event.add_attribute("commission_recipient", commission_recipient);
+        }
+
+        if let Some(max_bid_per_bidder) = max_bid_per_bidder {
+            MAX_BID_PER_BIDDER.save(deps.storage, &max_bid_per_bidder)?;
+            event = event.add_attribute("max_bid_per_bidder", max_bid_per_bidder);
+        }
+
+        if let Some(guardian) = guardian {
+            let guardian = deps.api.addr_validate(&guardian)?;
+            GUARDIAN.save(deps.storage, &guardian)?;
+            event = event.add_attribute("guardian", guardian);
+        }
+
+        if let Some(retract_cooldown) = retract_cooldown {
+            RETRACT_COOLDOWN.save(deps.storage, &retract_cooldown)?;
+            event = event.add_attribute("retract_cooldown", retract_cooldown.to_string());
+        }
+
+        if let Some(refund_keeper_tip) = refund_keeper_tip {
+            REFUND_KEEPER_TIP.save(deps.storage, &refund_keeper_tip)?;
+            event = event.add_attribute("refund_keeper_tip", refund_keeper_tip.to_string());
+        }
+
+        if let Some(arbiter) = arbiter {
+            let arbiter = deps.api.addr_validate(&arbiter)?;
+            ARBITER.save(deps.storage, &arbiter)?;
+            event = event.add_attribute("arbiter", arbiter);
+        }
+
+        if let Some(delivery_timeout) = delivery_timeout {
+            DELIVERY_TIMEOUT.save(deps.storage, &delivery_timeout)?;
+            event = event.add_attribute("delivery_timeout", delivery_timeout.to_string());
+        }
+
+        // cleared settings are listed as "none"
+        for setting in clear {
+            let key = match setting {
+                OptionalSetting::MaxBidPerBidder => {
+                    MAX_BID_PER_BIDDER.remove(deps.storage);
+                    "max_bid_per_bidder"
+                }
+                OptionalSetting::RetractCooldown => {
+                    RETRACT_COOLDOWN.remove(deps.storage);
+                    "retract_cooldown"
+                }
+                OptionalSetting::Arbiter => {
+                    ARBITER.remove(deps.storage);
+                    "arbiter"
+                }
+            };
+            event = event.add_attribute(key, "none");
+        }
+
+        Ok(Response::new()
+            .add_event(event)
+            .add_attribute("action", "update_config")
+            .add_attribute("sender", info.sender))
+    }
+
+    pub fn update_item(
+        deps: DepsMut,
+        info: MessageInfo,
+        item: ItemMetadata,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
+
+        // bidders have to get the item they bid on
+        if bidding_started(deps.storage) {
+            return Err(ContractError::BiddingStarted {});
+        }
+
+        validate_item(&AUCTION_ITEM_TITLE.load(deps.storage)?, &item)?;
+        ITEM_METADATA.save(deps.storage, &item)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_item")
+            .add_attribute("sender", info.sender))
+    }
+
+    /// Loads the escrow, failing unless it is still waiting to be settled.
+    fn pending_escrow(storage: &dyn Storage) -> Result<DeliveryEscrow, ContractError> {
+        match ESCROW.may_load(storage)? {
+            Some(
+                escrow @ DeliveryEscrow {
+                    status: EscrowStatus::AwaitingDelivery | EscrowStatus::Disputed,
+                    ..
+                },
+            ) => Ok(escrow),
+            _ => Err(ContractError::NoPendingEscrow {}),
+        }
+    }
 
-// version info for migration info
-const CONTRACT_NAME: &str = "crates.io:cw-academy-auction";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+    /// Tells the companion holding a remote winner's funds how to split them.
+    fn payout_packet(
+        storage: &dyn Storage,
+        env: &Env,
+        escrow: &DeliveryEscrow,
+        channel_id: &str,
+        buyer_share: Decimal,
+    ) -> Result<IbcMsg, ContractError> {
+        let winner = REMOTE_BIDDERS.load(storage, &escrow.winner)?;
 
-const BID_DENOM: &str = "ubtc";
+        Ok(auction_packet(
+            env,
+            channel_id,
+            &AuctionPacketMsg::Payout {
+                winner: winner.bidder,
+                seller: OWNER.load(storage)?.to_string(),
+                buyer_share,
+                commission: COMMISSION_PERCENTAGE.load(storage)?,
+                commission_recipient: COMMISSION_RECIPIENT.load(storage)?.to_string(),
+            },
+        )?)
+    }
 
-pub fn calc_highest_bid(deps: Deps) -> (String, Coin) {
-    BIDS.range(deps.storage, None, None, Order::Ascending)
-        .map(|v| v.unwrap_or((String::new(), Coin::new(0, "utoken"))))
-        .reduce(
-            |(highest_bidder_address, highest_bid), (bidder_address, bidder_amount)| {
-                if highest_bid.amount > bidder_amount.amount {
-                    (highest_bidder_address, highest_bid)
-                } else {
-                    (bidder_address, bidder_amount)
-                }
+    /// Pays `buyer_share` of the escrow back to the winner and the rest to the seller.
+    fn settle_escrow(
+        mut deps: DepsMut,
+        env: &Env,
+        mut escrow: DeliveryEscrow,
+        buyer_share: Decimal,
+    ) -> Result<Response, ContractError> {
+        ensure_settlement_open(deps.storage)?;
+        // a staked winning bid is only back in the contract once unbonding completes
+        ensure_unbonded(deps.branch(), env)?;
+
+        escrow.status = EscrowStatus::Settled;
+        escrow.buyer_share = Some(buyer_share);
+        ESCROW.save(deps.storage, &escrow)?;
+
+        let hook_msgs = prepare_hooks(
+            deps.storage,
+            AuctionHookMsg::Settled {
+                winner: escrow.winner.to_string(),
+                amount: escrow.bid.clone(),
             },
-        )
-        .unwrap()
-}
+        )?;
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response, ContractError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        // the companion holding a remote winner's bid splits it the same way
+        let funds = match escrow.remote_channel {
+            Some(_) => vec![escrow.bid.clone()],
+            None => escrow.funds.clone(),
+        };
 
-    let owner_addr = msg.owner.unwrap_or(info.sender.to_string());
-    OWNER.save(deps.storage, &owner_addr)?;
+        // the commission is taken from the seller's share only
+        let commission = COMMISSION_PERCENTAGE.load(deps.storage)?;
+        let mut buyer_funds = vec![];
+        let mut seller_funds = vec![];
+        let mut commission_funds = vec![];
+        for coin in &funds {
+            let buyer_amount = coin.amount * buyer_share;
+            let commission_amount = (coin.amount - buyer_amount) * commission;
+            add_coin(
+                &mut buyer_funds,
+                Coin::new(buyer_amount.u128(), &coin.denom),
+            );
+            add_coin(
+                &mut commission_funds,
+                Coin::new(commission_amount.u128(), &coin.denom),
+            );
+            add_coin(
+                &mut seller_funds,
+                Coin::new(
+                    (coin.amount - buyer_amount - commission_amount).u128(),
+                    &coin.denom,
+                ),
+            );
+        }
 
-    let commission = msg
-        .commission_percentage
-        .unwrap_or(Decimal::new(Uint128::new(50_000_000_000_000_000)));
-    COMMISSION_PERCENTAGE.save(deps.storage, &commission)?;
+        let mut response = Response::new().add_event(Event::from(SettleEvent {
+            auction_id: env.contract.address.clone(),
+            bidder: escrow.winner.to_string(),
+            new_total: escrow.bid.clone(),
+            amount: seller_funds.clone(),
+            commission: commission_funds.clone(),
+        }));
 
-    AUCTION_ITEM_TITLE.save(deps.storage, &msg.auction_item_title)?;
-    ACTIVE.save(deps.storage, &true)?;
+        if let Some(channel_id) = &escrow.remote_channel {
+            let payout = payout_packet(deps.storage, env, &escrow, channel_id, buyer_share)?;
+            return Ok(response
+                .add_message(payout)
+                .add_submessages(hook_msgs)
+                .add_attribute("winner", escrow.winner));
+        }
 
-    let zero_coin = Coin::new(0, "ubtc");
+        if !seller_funds.is_empty() {
+            response = response.add_message(BankMsg::Send {
+                to_address: OWNER.load(deps.storage)?.to_string(),
+                amount: seller_funds,
+            });
+        }
+        if !commission_funds.is_empty() {
+            response = response.add_message(BankMsg::Send {
+                to_address: COMMISSION_RECIPIENT.load(deps.storage)?.to_string(),
+                amount: commission_funds,
+            });
+        }
+        if !buyer_funds.is_empty() {
+            response = response.add_event(Event::from(RefundEvent {
+                auction_id: env.contract.address.clone(),
+                bidder: escrow.winner.to_string(),
+                amount: buyer_funds.clone(),
+            }));
+        }
 
-    let new_bid_funds = info
-        .funds
-        .iter()
-        .find(|coin| coin.denom == BID_DENOM)
-        .unwrap_or(&zero_coin);
+        Ok(response
+            .add_submessages(refund_msgs(deps.storage, env, &escrow.winner, buyer_funds)?)
+            .add_submessages(hook_msgs)
+            .add_attribute("winner", escrow.winner))
+    }
 
-    BIDS.save(deps.storage, info.sender.to_string(), new_bid_funds)?;
+    pub fn confirm_delivery(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let escrow = pending_escrow(deps.storage)?;
+        if info.sender != escrow.winner {
+            return Err(ContractError::Unauthorized {
+                owner: escrow.winner.to_string(),
+            });
+        }
 
-    Ok(Response::new()
-        .add_attribute("method", "instantiate")
-        .add_attribute("sender", info.sender))
-}
+        Ok(settle_escrow(deps, &env, escrow, Decimal::zero())?
+            .add_attribute("action", "confirm_delivery")
+            .add_attribute("sender", info.sender))
+    }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::Bid {} => execute::bid(deps, info),
-        ExecuteMsg::CloseBidding {} => execute::close(deps, info),
-        ExecuteMsg::RetractFunds { withdraw_address } => {
-            execute::retract(deps, info, withdraw_address)
+    pub fn release_escrow(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let escrow = pending_escrow(deps.storage)?;
+        if escrow.status == EscrowStatus::Disputed {
+            return Err(ContractError::EscrowDisputed {});
+        }
+
+        if env.block.time < escrow.release_at {
+            return Err(ContractError::DeliveryPending {
+                release_at: escrow.release_at,
+            });
         }
+
+        Ok(settle_escrow(deps, &env, escrow, Decimal::zero())?
+            .add_attribute("action", "release_escrow")
+            .add_attribute("sender", info.sender))
     }
-}
 
-pub mod execute {
-    use cosmwasm_std::{BankMsg, Coin, StakingMsg};
+    pub fn open_dispute(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        let mut escrow = pending_escrow(deps.storage)?;
 
-    use crate::state::BIDS;
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != escrow.winner && info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
+        }
 
-    use super::*;
+        if ARBITER.may_load(deps.storage)?.is_none() {
+            return Err(ContractError::NoArbiter {});
+        }
 
-    pub fn bid(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-        let auction_active = ACTIVE.load(deps.storage)?;
-        if !auction_active {
-            return Err(ContractError::AuctionInactive {});
+        if escrow.status == EscrowStatus::Disputed {
+            return Err(ContractError::EscrowDisputed {});
         }
 
-        let zero_coin = Coin::new(0, "ubtc");
+        escrow.status = EscrowStatus::Disputed;
+        ESCROW.save(deps.storage, &escrow)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "open_dispute")
+            .add_attribute("sender", info.sender))
+    }
+
+    pub fn resolve_dispute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        buyer_share: Decimal,
+    ) -> Result<Response, ContractError> {
+        let arbiter = ARBITER
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoArbiter {})?;
+        if info.sender != arbiter {
+            return Err(ContractError::Unauthorized {
+                owner: arbiter.to_string(),
+            });
+        }
 
-        let new_bid_funds = info
-            .funds
-            .iter()
-            .find(|coin| coin.denom == BID_DENOM)
-            .unwrap_or(&zero_coin);
+        let escrow = pending_escrow(deps.storage)?;
+        if escrow.status != EscrowStatus::Disputed {
+            return Err(ContractError::EscrowNotDisputed {});
+        }
 
-        if new_bid_funds.amount.le(&Uint128::zero()) {
-            return Err(ContractError::InvalidBidAmount {});
+        if buyer_share > Decimal::one() {
+            return Err(ContractError::InvalidBuyerShare {});
         }
 
-        let (highest_bid_address, highest_bid) = calc_highest_bid(deps.as_ref());
+        Ok(settle_escrow(deps, &env, escrow, buyer_share)?
+            .add_attribute("action", "resolve_dispute")
+            .add_attribute("sender", info.sender)
+            .add_attribute("buyer_share", buyer_share.to_string()))
+    }
 
-        let previous_bid = if highest_bid_address == info.sender.to_string() {
-            highest_bid.clone()
-        } else {
-            BIDS.may_load(deps.storage, info.sender.to_string())?
-                .unwrap_or(zero_coin.clone())
-        };
+    pub fn retry_payout(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        ensure_settlement_open(deps.storage)?;
 
-        let new_bid = Coin {
-            denom: "ubtc".to_string(),
-            amount: previous_bid.amount + new_bid_funds.amount,
+        let mut escrow = match ESCROW.may_load(deps.storage)? {
+            Some(escrow) if escrow.status == EscrowStatus::PayoutFailed => escrow,
+            _ => return Err(ContractError::NoFailedPayout {}),
         };
+        let channel_id = escrow
+            .remote_channel
+            .as_deref()
+            .ok_or(ContractError::NoFailedPayout {})?;
+        let buyer_share = escrow.buyer_share.unwrap_or_default();
 
-        if highest_bid.amount >= new_bid.amount {
-            return Err(ContractError::BidTooLow {
-                minimum_bid_amount: highest_bid.amount.u128(),
-                bid_denom: "ubtc".to_string(),
-                current_bid_amount: previous_bid.amount.u128(),
+        let payout = payout_packet(deps.storage, &env, &escrow, channel_id, buyer_share)?;
+        escrow.status = EscrowStatus::Settled;
+        ESCROW.save(deps.storage, &escrow)?;
+
+        Ok(Response::new()
+            .add_message(payout)
+            .add_attribute("action", "retry_payout")
+            .add_attribute("sender", info.sender)
+            .add_attribute("winner", escrow.winner))
+    }
+
+    pub fn allow_ibc_counterparty(
+        deps: DepsMut,
+        info: MessageInfo,
+        connection_id: String,
+        port_id: String,
+    ) -> Result<Response, ContractError> {
+        let owner = OWNER.load(deps.storage)?;
+        if info.sender != owner {
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
             });
         }
 
-        BIDS.save(deps.storage, info.sender.to_string(), &new_bid)?;
+        IBC_COUNTERPARTIES.save(deps.storage, (&connection_id, &port_id), &Empty {})?;
 
         Ok(Response::new()
-            .add_attribute("action", "bid")
-            .add_attribute("sender", info.sender.to_string())
-            .add_attribute("bid_amount", new_bid.to_string()))
+            .add_attribute("action", "allow_ibc_counterparty")
+            .add_attribute("sender", info.sender)
+            .add_attribute("connection_id", connection_id)
+            .add_attribute("port_id", port_id))
     }
 
-    pub fn close(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    pub fn remove_ibc_counterparty(
+        deps: DepsMut,
+        info: MessageInfo,
+        connection_id: String,
+        port_id: String,
+    ) -> Result<Response, ContractError> {
         let owner = OWNER.load(deps.storage)?;
         if info.sender != owner {
-            return Err(ContractError::Unauthorized { owner: owner });
+            return Err(ContractError::Unauthorized {
+                owner: owner.to_string(),
+            });
         }
 
-        let auction_active = ACTIVE.load(deps.storage)?;
-        if !auction_active {
-            return Err(ContractError::AuctionInactive {});
+        IBC_COUNTERPARTIES.remove(deps.storage, (&connection_id, &port_id));
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_ibc_counterparty")
+            .add_attribute("sender", info.sender)
+            .add_attribute("connection_id", connection_id)
+            .add_attribute("port_id", port_id))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match (msg.id, msg.result) {
+        // hooks are best effort, a failing subscriber must not revert the auction
+        (HOOK_REPLY_ID, SubMsgResult::Err(err)) => Ok(Response::new()
+            .add_attribute("action", "hook_failed")
+            .add_attribute("error", err)),
+        (HOOK_REPLY_ID, SubMsgResult::Ok(_)) => Ok(Response::new()),
+        (STAKING_REPLY_ID, SubMsgResult::Ok(_)) => collect_staking_rewards(deps, env),
+        (IBC_REFUND_REPLY_ID, result) => track_ibc_refund(deps, result),
+        (id, _) => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+/// Tracks a sent IBC refund by its packet sequence, or credits it back right away when the
+/// transfer could not be sent at all. A refund sent without a known sequence can't be matched
+/// to its outcome, it is left untracked rather than failing the bid or batch that sent it.
+fn track_ibc_refund(deps: DepsMut, result: SubMsgResult) -> Result<Response, ContractError> {
+    let refund = IBC_REFUNDS_SENDING
+        .pop_front(deps.storage)?
+        .ok_or_else(|| StdError::not_found("PendingIbcRefund"))?;
+    let bidder = refund.bidder.clone();
+
+    match result {
+        SubMsgResult::Ok(response) => {
+            let sequence = match response.data.as_deref().and_then(ics20::transfer_sequence) {
+                Some(sequence) => sequence,
+                None => {
+                    return Ok(Response::new()
+                        .add_attribute("action", "ibc_refund_untracked")
+                        .add_attribute("bidder", bidder)
+                        .add_attribute("amount", refund.amount.to_string()))
+                }
+            };
+            IBC_REFUNDS_IN_FLIGHT.save(deps.storage, (&refund.channel_id, sequence), &refund)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "ibc_refund_sent")
+                .add_attribute("bidder", bidder)
+                .add_attribute("sequence", sequence.to_string()))
         }
+        SubMsgResult::Err(err) => {
+            credit_failed_refund(deps.storage, refund)?;
 
-        ACTIVE.save(deps.storage, &false)?;
+            Ok(Response::new()
+                .add_attribute("action", "ibc_refund_failed")
+                .add_attribute("bidder", bidder)
+                .add_attribute("error", err))
+        }
+    }
+}
 
-        Ok(Response::new()
-            .add_attribute("action", "close_bidding")
-            .add_attribute("sender", info.sender.to_string()))
+/// Outcome of IBC refunds reported by the ibc-hooks middleware. The transfer module returns the
+/// funds of a failed or timed out transfer, so they are credited back to the bidder.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let SudoMsg::IbcLifecycleComplete(lifecycle) = msg;
+    let (channel, sequence, success) = match lifecycle {
+        IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        } => (channel, sequence, success),
+        IbcLifecycleComplete::IbcTimeout { channel, sequence } => (channel, sequence, false),
+    };
+
+    let refund = match IBC_REFUNDS_IN_FLIGHT.may_load(deps.storage, (&channel, sequence))? {
+        Some(refund) => refund,
+        None => return Ok(Response::new()),
+    };
+    IBC_REFUNDS_IN_FLIGHT.remove(deps.storage, (&channel, sequence));
+
+    let bidder = refund.bidder.clone();
+    if !success {
+        credit_failed_refund(deps.storage, refund)?;
     }
 
-    pub fn retract(
-        deps: DepsMut,
-        info: MessageInfo,
-        withdraw_address: Option<String>,
-    ) -> Result<Response, ContractError> {
-        let auction_active = ACTIVE.load(deps.storage)?;
-        if auction_active {
-            return Err(ContractError::AuctionActive {});
+    Ok(Response::new()
+        .add_attribute("action", "ibc_refund_complete")
+        .add_attribute("bidder", bidder)
+        .add_attribute("success", success.to_string()))
+}
+
+/// Rewards paid out by a delegation change are whatever the liquid balance grew beyond the
+/// expected one. After close they go into the snapshot, before it they are collected.
+fn collect_staking_rewards(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let expected = STAKING_EXPECTED_BALANCE.load(deps.storage)?;
+    STAKING_EXPECTED_BALANCE.remove(deps.storage);
+
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, BID_DENOM)?
+        .amount;
+    let rewards = balance.saturating_sub(expected);
+
+    match STAKING_REWARDS.may_load(deps.storage)? {
+        Some(mut staking_rewards) => {
+            staking_rewards.total_rewards += rewards;
+            staking_rewards.balance_at_close = balance;
+            STAKING_REWARDS.save(deps.storage, &staking_rewards)?;
         }
+        None => {
+            COLLECTED_REWARDS.update(deps.storage, |collected| -> StdResult<_> {
+                Ok(collected + rewards)
+            })?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "collect_staking_rewards")
+        .add_attribute("rewards", rewards))
+}
 
-        let (highest_bidder_address, _) = calc_highest_bid(deps.as_ref());
+/// Upgrades any earlier deployment, down to the first release. Addresses used to be stored as
+/// unvalidated strings, so entries under a non canonical spelling of an address are moved to the
+/// canonical one, merging them with existing bids. Settings added since are set to their
+/// defaults and bids placed before deposits were tracked get their deposit recorded.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let version = get_contract_version(deps.storage)?;
+    if version.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigration {
+            contract: version.contract,
+        });
+    }
 
-        if info.sender.to_string() == highest_bidder_address {
-            return Err(ContractError::NothingToWithdraw {});
+    let rewritten = migrate::addr_keys(deps.storage, deps.api)?;
+    migrate::default_settings(deps.storage)?;
+    migrate::legacy_bids(deps.storage, &env)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", version.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("rewritten_bids", rewritten.to_string()))
+}
+
+pub mod migrate {
+    use super::*;
+    use cosmwasm_std::Api;
+    use cw_storage_plus::{Item, Map};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    fn canonical(api: &dyn Api, addr: &str) -> StdResult<Addr> {
+        api.addr_humanize(&api.addr_canonicalize(addr)?)
+    }
+
+    /// Rewrites an address item to its canonical spelling, if it is set.
+    fn canonical_item(storage: &mut dyn Storage, api: &dyn Api, item: Item<Addr>) -> StdResult<()> {
+        if let Some(addr) = item.may_load(storage)? {
+            item.save(storage, &canonical(api, addr.as_str())?)?;
         }
 
-        let withdrawl = match BIDS.may_load(deps.storage, info.sender.to_string()) {
-            Ok(Some(bid)) if bid.amount != Uint128::new(0) => bid,
-            _ => return Err(ContractError::NothingToWithdraw {}),
-        };
+        Ok(())
+    }
+
+    /// Saves `default` unless the item is already set.
+    fn default_item<T>(storage: &mut dyn Storage, item: Item<T>, default: T) -> StdResult<()>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if item.may_load(storage)?.is_none() {
+            item.save(storage, &default)?;
+        }
 
-        let to_address = withdraw_address.unwrap_or(info.sender.to_string());
+        Ok(())
+    }
 
-        BIDS.save(
-            deps.storage,
-            info.sender.to_string(),
-            &Coin::new(0, BID_DENOM),
-        )?;
+    /// Moves the entry of `from` to `to`, keeping the existing entry of `to` if there is one.
+    fn move_entry<T>(
+        storage: &mut dyn Storage,
+        map: Map<&Addr, T>,
+        from: &Addr,
+        to: &Addr,
+    ) -> StdResult<()>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if let Some(value) = map.may_load(storage, from)? {
+            map.remove(storage, from);
+            if !map.has(storage, to) {
+                map.save(storage, to, &value)?;
+            }
+        }
 
-        StakingMsg::Delegate { validator: (), amount: () }
+        Ok(())
+    }
 
-        Ok(Response::new()
-            .add_message(BankMsg::Send {
-                to_address,
-                amount: vec![withdrawl],
-            })
-            .add_attribute("action", "retract_funds")
-            .add_attribute("sender", info.sender))
+    /// Rewrites the configured addresses, the hooks and every bidder keyed by a non canonical address,
+    /// returning the number of bids moved.
+    pub fn addr_keys(storage: &mut dyn Storage, api: &dyn Api) -> StdResult<u32> {
+        canonical_item(storage, api, OWNER)?;
+        canonical_item(storage, api, GUARDIAN)?;
+        canonical_item(storage, api, ARBITER)?;
+
+        // keys are read back unchecked, which is what lets the legacy spellings be found
+        let bids = BIDS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<(Addr, Coin)>>>()?;
+
+        let mut rewritten = 0;
+        for (key, bid) in bids {
+            // remote bidders are keyed by their channel, they aren't addresses of this chain
+            if REMOTE_BIDDERS.has(storage, &key) {
+                continue;
+            }
+
+            let bidder = canonical(api, key.as_str())?;
+            if bidder == key {
+                continue;
+            }
+
+            BIDS.remove(storage, &key);
+            BIDS.update(storage, &bidder, |current| -> StdResult<_> {
+                Ok(match current {
+                    Some(current) => Coin::new((current.amount + bid.amount).u128(), BID_DENOM),
+                    None => bid,
+                })
+            })?;
+
+            let deposits = take_deposits(storage, &key)?;
+            add_deposits(storage, &bidder, &deposits)?;
+
+            // the latest bid decides when the merged bid can be retracted
+            if let Some(last_bid_at) = LAST_BID_AT.may_load(storage, &key)? {
+                LAST_BID_AT.remove(storage, &key);
+                LAST_BID_AT.update(storage, &bidder, |current| -> StdResult<_> {
+                    Ok(current.map_or(last_bid_at, |current| current.max(last_bid_at)))
+                })?;
+            }
+            move_entry(storage, REFUND_ADDRESSES, &key, &bidder)?;
+            move_entry(storage, IBC_REFUND_ROUTES, &key, &bidder)?;
+            move_entry(storage, STAKE_WEIGHTS, &key, &bidder)?;
+
+            rewritten += 1;
+        }
+
+        let hooks = HOOKS
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<Addr>>>()?;
+        for key in hooks {
+            move_entry(storage, HOOKS, &key, &canonical(api, key.as_str())?)?;
+        }
+
+        Ok(rewritten)
+    }
+
+    /// Sets every setting the deployment predates to the default instantiation would pick.
+    pub fn default_settings(storage: &mut dyn Storage) -> StdResult<()> {
+        let owner = OWNER.load(storage)?;
+        default_item(storage, GUARDIAN, owner.clone())?;
+        default_item(storage, COMMISSION_RECIPIENT, owner)?;
+        default_item(storage, ITEM_METADATA, ItemMetadata::default())?;
+        default_item(storage, PAUSED, false)?;
+        default_item(storage, SETTLEMENT_PAUSED, false)?;
+        default_item(storage, AUTO_REFUND_OUTBID, false)?;
+        default_item(storage, REFUND_KEEPER_TIP, Decimal::zero())?;
+        default_item(storage, DELIVERY_TIMEOUT, DEFAULT_DELIVERY_TIMEOUT)?;
+        default_item(storage, ACCEPTED_DENOMS, vec![])?;
+
+        Ok(())
+    }
+
+    /// Records the deposit and bid time of bids placed before either was tracked. The first
+    /// release saved an empty bid for whoever instantiated the auction, which is dropped.
+    pub fn legacy_bids(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+        let bids = BIDS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<(Addr, Coin)>>>()?;
+
+        for (bidder, bid) in bids {
+            if REMOTE_BIDDERS.has(storage, &bidder) {
+                continue;
+            }
+
+            if bid.amount.is_zero() {
+                BIDS.remove(storage, &bidder);
+                continue;
+            }
+
+            // bids could only be placed in the bid denom back then
+            if deposits_of(storage, &bidder)?.is_empty() {
+                DEPOSITS.save(storage, (&bidder, &bid.denom), &bid.amount)?;
+            }
+            if !LAST_BID_AT.has(storage, &bidder) {
+                LAST_BID_AT.save(storage, &bidder, &env.block.time)?;
+            }
+        }
+
+        Ok(())
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetAuctionStatus {} => to_binary(&query::status(deps)?),
+        QueryMsg::GetConfig {} => to_binary(&query::config(deps)?),
+        QueryMsg::GetItem {} => to_binary(&query::item(deps)?),
         QueryMsg::GetUserBid { bidder } => to_binary(&query::get_user_bid(deps, bidder)?),
+        QueryMsg::GetUserDeposits { bidder } => to_binary(&query::get_user_deposits(deps, bidder)?),
+        QueryMsg::GetBlocklist { start_after, limit } => {
+            to_binary(&query::blocklist(deps, start_after, limit)?)
+        }
+        QueryMsg::GetHooks {} => to_binary(&query::hooks(deps)?),
+        QueryMsg::GetEscrow {} => to_binary(&ESCROW.may_load(deps.storage)?),
+        QueryMsg::SimulateBid {
+            bidder,
+            amount,
+            proof,
+        } => to_binary(&query::simulate_bid(deps, bidder, amount, proof)?),
+        QueryMsg::GetBidderStatus { bidder } => {
+            to_binary(&query::bidder_status(deps, env, bidder)?)
+        }
     }
 }
 
 pub mod query {
-    use cosmwasm_std::{coin, Coin, Order};
+    use cosmwasm_std::{coin, Order};
+    use cw_storage_plus::Bound;
 
     use crate::{
-        msg::{AuctionStatusResponse, BidResponse},
+        msg::{
+            AuctionStatusResponse, BidResponse, BidderStatusResponse, BlocklistResponse,
+            ConfigResponse, DepositsResponse, HooksResponse, ItemResponse, SimulateBidResponse,
+        },
         state::BIDS,
     };
 
@@ -217,45 +2328,241 @@ pub mod query {
     pub fn status(deps: Deps) -> StdResult<AuctionStatusResponse> {
         let owner = OWNER.load(deps.storage)?;
         let active = ACTIVE.load(deps.storage)?;
+        let paused = PAUSED.load(deps.storage)?;
+        let settlement_paused = SETTLEMENT_PAUSED.load(deps.storage)?;
         let auction_item_title = AUCTION_ITEM_TITLE.load(deps.storage)?;
         let commission_percentage = COMMISSION_PERCENTAGE.load(deps.storage)?;
 
-        let all_bids: Vec<(String, Coin)> = BIDS
-            .range(deps.storage, None, None, Order::Ascending)
-            .map(|v| v.unwrap_or((String::new(), Coin::new(0, "utoken"))))
-            .collect();
-
-        let (bidder, bid) = all_bids
-            .iter()
-            .map(|(addr, bid)| (addr, bid))
-            .reduce(
-                |(highest_bidder_address, highest_bid), (bidder_address, bidder_amount)| {
-                    if highest_bid.amount > bidder_amount.amount {
-                        (highest_bidder_address, highest_bid)
-                    } else {
-                        (bidder_address, bidder_amount)
-                    }
-                },
-            )
-            .unwrap();
+        let highest_bid = calc_highest_bid(deps)?.map(|(bidder, bid)| BidResponse {
+            bidder: bidder.to_string(),
+            bid,
+        });
+        let bidders_count = BIDS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count();
 
         Ok(AuctionStatusResponse {
-            owner,
+            owner: owner.to_string(),
             active,
+            paused,
+            settlement_paused,
             auction_item_title,
-            highest_bid: BidResponse {
-                bidder: bidder.to_string(),
-                bid: bid.clone(),
-            },
-            bidders_count: all_bids.len(),
+            highest_bid,
+            bidders_count,
             commission_percentage,
         })
     }
 
+    pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+        Ok(ConfigResponse {
+            owner: OWNER.load(deps.storage)?.to_string(),
+            guardian: GUARDIAN.load(deps.storage)?.to_string(),
+            arbiter: ARBITER.may_load(deps.storage)?.map(String::from),
+            auction_item_title: AUCTION_ITEM_TITLE.load(deps.storage)?,
+            bid_denom: BID_DENOM.to_string(),
+            accepted_denoms: ACCEPTED_DENOMS.may_load(deps.storage)?.unwrap_or_default(),
+            price_source: PRICE_SOURCE.may_load(deps.storage)?,
+            commission_percentage: COMMISSION_PERCENTAGE.load(deps.storage)?,
+            commission_recipient: COMMISSION_RECIPIENT.load(deps.storage)?.to_string(),
+            refund_keeper_tip: REFUND_KEEPER_TIP.load(deps.storage)?,
+            max_bid_per_bidder: MAX_BID_PER_BIDDER.may_load(deps.storage)?,
+            auto_refund_outbid: AUTO_REFUND_OUTBID.load(deps.storage)?,
+            retract_cooldown: RETRACT_COOLDOWN.may_load(deps.storage)?,
+            delivery_timeout: DELIVERY_TIMEOUT.load(deps.storage)?,
+            merkle_root: MERKLE_ROOT.may_load(deps.storage)?,
+            staking: STAKING_CONFIG.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn item(deps: Deps) -> StdResult<ItemResponse> {
+        Ok(ItemResponse {
+            title: AUCTION_ITEM_TITLE.load(deps.storage)?,
+            metadata: ITEM_METADATA.load(deps.storage)?,
+        })
+    }
+
     pub fn get_user_bid(deps: Deps, bidder: String) -> StdResult<BidResponse> {
+        let bidder = bidder_key(deps, &bidder)?;
+        let bid = BIDS
+            .may_load(deps.storage, &bidder)?
+            .unwrap_or(coin(0, BID_DENOM));
+        Ok(BidResponse {
+            bidder: bidder.to_string(),
+            bid,
+        })
+    }
+
+    pub fn get_user_deposits(deps: Deps, bidder: String) -> StdResult<DepositsResponse> {
+        let bidder = bidder_key(deps, &bidder)?;
+        let deposits = deposits_of(deps.storage, &bidder)?;
+        Ok(DepositsResponse {
+            bidder: bidder.to_string(),
+            deposits,
+        })
+    }
+
+    pub fn simulate_bid(
+        deps: Deps,
+        bidder: String,
+        funds: Vec<Coin>,
+        proof: Option<Vec<String>>,
+    ) -> StdResult<SimulateBidResponse> {
+        let bidder = deps.api.addr_validate(&bidder)?;
+
+        let checked = ensure_bidding_open(deps.storage)
+            .and_then(|_| verify_allowlist(deps, bidder.as_str(), proof))
+            .and_then(|_| ensure_not_blocklisted(deps.storage, bidder.as_str()))
+            .and_then(|_| check_bid(deps, &bidder, &funds));
+
+        let previous_bid = BIDS
+            .may_load(deps.storage, &bidder)?
+            .unwrap_or(coin(0, BID_DENOM));
+        let highest_amount = calc_highest_bid(deps)?
+            .map(|(_, highest_bid)| highest_bid.amount)
+            .unwrap_or_default();
+        // the new total has to exceed the leading bid, which may be the bidder's own
+        let minimum_required =
+            (highest_amount + Uint128::one()).saturating_sub(previous_bid.amount);
+
+        Ok(match checked {
+            Ok(checked) => SimulateBidResponse {
+                success: true,
+                new_total: checked.new_bid,
+                would_lead: true,
+                minimum_required: coin(minimum_required.u128(), BID_DENOM),
+                error: None,
+            },
+            Err(err) => {
+                let increment = bid_value(deps, &funds).unwrap_or_default();
+                SimulateBidResponse {
+                    success: false,
+                    new_total: coin((previous_bid.amount + increment).u128(), BID_DENOM),
+                    would_lead: false,
+                    minimum_required: coin(minimum_required.u128(), BID_DENOM),
+                    error: Some(err.to_string()),
+                }
+            }
+        })
+    }
+
+    pub fn bidder_status(deps: Deps, env: Env, bidder: String) -> StdResult<BidderStatusResponse> {
+        let bidder = bidder_key(deps, &bidder)?;
         let bid = BIDS
-            .may_load(deps.storage, bidder.to_string())?
-            .unwrap_or(coin(0, "ubtc"));
-        Ok(BidResponse { bidder, bid })
+            .may_load(deps.storage, &bidder)?
+            .unwrap_or(coin(0, BID_DENOM));
+
+        let highest = calc_highest_bid(deps)?;
+        let leading = matches!(&highest, Some((leader, _)) if *leader == bidder);
+        let amount_to_lead = match &highest {
+            Some((_, highest_bid)) if !leading => highest_bid.amount + Uint128::one() - bid.amount,
+            Some(_) => Uint128::zero(),
+            None => Uint128::one(),
+        };
+
+        // ties are won by the later address, like for the leading bid
+        let rank = if bid.amount.is_zero() {
+            None
+        } else {
+            let mut ahead = 0;
+            for other in BIDS.range(deps.storage, None, None, Order::Ascending) {
+                let (other, other_bid) = other?;
+                if other_bid.amount > bid.amount
+                    || (other_bid.amount == bid.amount && other > bidder)
+                {
+                    ahead += 1;
+                }
+            }
+            Some(ahead + 1)
+        };
+
+        let staking_rewards = STAKING_REWARDS.may_load(deps.storage)?;
+        let staking_reward = match &staking_rewards {
+            Some(staking_rewards) => {
+                pending_staking_reward(deps, staking_rewards, &bidder, bid.amount)?
+            }
+            None => Uint128::zero(),
+        };
+
+        let mut pending_refunds = if leading {
+            vec![]
+        } else {
+            unstaked_funds(
+                staking_rewards.as_ref(),
+                deposits_of(deps.storage, &bidder)?,
+            )
+        };
+        add_coin(
+            &mut pending_refunds,
+            Coin::new(staking_reward.u128(), BID_DENOM),
+        );
+        let failed_refunds = FAILED_REFUNDS
+            .may_load(deps.storage, &bidder)?
+            .unwrap_or_default();
+        for coin in &failed_refunds {
+            add_coin(&mut pending_refunds, coin.clone());
+        }
+
+        let withdrawable = match withdrawable_bid(deps, &env, &bidder) {
+            Ok((_, available)) => {
+                let mut withdrawable = if available.is_zero() {
+                    vec![]
+                } else {
+                    unstaked_funds(
+                        staking_rewards.as_ref(),
+                        deposits_of(deps.storage, &bidder)?,
+                    )
+                };
+                add_coin(
+                    &mut withdrawable,
+                    Coin::new(staking_reward.u128(), BID_DENOM),
+                );
+                for coin in failed_refunds {
+                    add_coin(&mut withdrawable, coin);
+                }
+                withdrawable
+            }
+            Err(ContractError::Std(err)) => return Err(err),
+            Err(_) => vec![],
+        };
+
+        Ok(BidderStatusResponse {
+            bidder: bidder.to_string(),
+            bid,
+            rank,
+            leading,
+            amount_to_lead: coin(amount_to_lead.u128(), BID_DENOM),
+            can_withdraw: !withdrawable.is_empty(),
+            withdrawable,
+            pending_refunds,
+        })
+    }
+
+    pub fn blocklist(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BlocklistResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let addresses = BLOCKLIST
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|address| address.map(String::from))
+            .collect::<StdResult<Vec<String>>>()?;
+
+        Ok(BlocklistResponse { addresses })
+    }
+
+    pub fn hooks(deps: Deps) -> StdResult<HooksResponse> {
+        let hooks = HOOKS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .map(|addr| addr.map(String::from))
+            .collect::<StdResult<Vec<String>>>()?;
+
+        Ok(HooksResponse { hooks })
     }
 }
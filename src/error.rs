@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError, Timestamp};
+use hex::FromHexError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +7,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Hex(#[from] FromHexError),
+
     #[error("Unauthorized. Action only permitted for {owner}")]
     Unauthorized { owner: String },
 
@@ -22,11 +26,128 @@ pub enum ContractError {
     #[error("Invalid bid amount")]
     InvalidBidAmount {},
 
+    #[error("Bids in {denom} are not accepted")]
+    UnsupportedDenom { denom: String },
+
+    #[error("A price source is required to accept more than one denom")]
+    MissingPriceSource {},
+
+    #[error("Partial withdrawals are only supported for bids made in the bid denom")]
+    PartialWithdrawalUnsupported {},
+
     #[error("Nothing to withdraw")]
     NothingToWithdraw {},
 
+    #[error("Cannot withdraw more than {available} {bid_denom}")]
+    WithdrawalTooLarge { available: u128, bid_denom: String },
+
+    #[error("Funds cannot be retracted until {available_at}")]
+    RetractCooldown { available_at: Timestamp },
+
     #[error("Cannot perform action while auction is active")]
     AuctionActive {},
+
+    #[error("Auction is paused")]
+    AuctionPaused {},
+
+    #[error("Settlement is paused")]
+    SettlementPaused {},
+
+    #[error("Bids can only be staked in the bonded denom {bonded_denom}")]
+    InvalidStakingDenom { bonded_denom: String },
+
+    #[error("Only the bonded denom can be accepted when bids are staked")]
+    StakingRequiresSingleDenom {},
+
+    #[error("Validator {validator} does not exist")]
+    UnknownValidator { validator: String },
+
+    #[error("Staked funds are still unbonding")]
+    FundsUnbonding {},
+
+    #[error("Outbid bids cannot be refunded automatically while bids are staked")]
+    AutoRefundWithStaking {},
+
+    #[error("Only the bidder can change their refund address or set it once they have bid")]
+    RefundAddressLocked {},
+
+    #[error("Only the bidder can set their IBC refund route")]
+    ForeignIbcRefundRoute {},
+
+    #[error("Invalid IBC refund route")]
+    InvalidIbcRefundRoute {},
+
+    #[error("Only unordered channels are supported")]
+    OrderedChannel {},
+
+    #[error("Invalid IBC channel version, expected {version}")]
+    InvalidIbcVersion { version: String },
+
+    #[error("Port {port_id} on connection {connection_id} is not an allowed counterparty")]
+    UnknownIbcCounterparty {
+        connection_id: String,
+        port_id: String,
+    },
+
+    #[error("No remote payout has failed")]
+    NoFailedPayout {},
+
+    #[error("Unexpected packet, only bids can be received")]
+    UnexpectedPacket {},
+
+    #[error("Remote bids cannot be staked")]
+    RemoteBidWithStaking {},
+
+    #[error("Item {field} exceeds the limit of {max}")]
+    ItemMetadataTooLarge { field: String, max: usize },
+
+    #[error("Bidding has already started")]
+    BiddingStarted {},
+
+    #[error("No delivery escrow is pending")]
+    NoPendingEscrow {},
+
+    #[error("Escrow can be released without confirmation after {release_at}")]
+    DeliveryPending { release_at: Timestamp },
+
+    #[error("Escrow is disputed and has to be resolved by the arbiter")]
+    EscrowDisputed {},
+
+    #[error("Escrow is not disputed")]
+    EscrowNotDisputed {},
+
+    #[error("No arbiter is configured for this auction")]
+    NoArbiter {},
+
+    #[error("Commission percentage must be between 0 and 1")]
+    InvalidCommission {},
+
+    #[error("Refund keeper tip cannot exceed {max}")]
+    RefundKeeperTipTooHigh { max: Decimal },
+
+    #[error("Buyer share must be between 0 and 1")]
+    InvalidBuyerShare {},
+
+    #[error("Funds are not accepted with this message")]
+    UnexpectedFunds {},
+
+    #[error("Cannot migrate from {contract}")]
+    InvalidMigration { contract: String },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Bidder is not on the auction allowlist")]
+    NotAllowlisted {},
+
+    #[error("{address} is blocked from bidding")]
+    Blocklisted { address: String },
+
+    #[error("Bid exceeds the per bidder maximum of {max_bid_amount} {bid_denom}")]
+    BidCapExceeded {
+        max_bid_amount: u128,
+        bid_denom: String,
+    },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -0,0 +1,118 @@
+//! Typed events emitted by the auction. The chain prefixes their types with `wasm-`, so indexers
+//! see `wasm-auction_bid`, `wasm-auction_close`, `wasm-auction_refund` and `wasm-auction_settle`.
+//!
+//! Every event carries the `auction_id` attribute, the address of the auction contract, and
+//! attributes describing the same thing always use the same key across events.
+
+use cosmwasm_std::{Addr, Coin, Event};
+
+pub const AUCTION_BID: &str = "auction_bid";
+pub const AUCTION_CLOSE: &str = "auction_close";
+pub const AUCTION_REFUND: &str = "auction_refund";
+pub const AUCTION_SETTLE: &str = "auction_settle";
+
+/// Address of the auction contract
+pub const ATTR_AUCTION_ID: &str = "auction_id";
+/// Bidder the event is about, the winner for closing and settlement
+pub const ATTR_BIDDER: &str = "bidder";
+/// Value added by a single bid
+pub const ATTR_INCREMENT: &str = "increment";
+/// Cumulative bid of the bidder after the event
+pub const ATTR_NEW_TOTAL: &str = "new_total";
+/// Bidder leading before the bid, which may be the bidder raising their own bid
+pub const ATTR_PREVIOUS_LEADER: &str = "previous_leader";
+/// Exact coins moved by a refund or settlement
+pub const ATTR_AMOUNT: &str = "amount";
+/// Coins of a settlement kept as commission
+pub const ATTR_COMMISSION: &str = "commission";
+
+fn format_coins(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// A bid was placed, emitted as `wasm-auction_bid`
+pub struct BidEvent {
+    pub auction_id: Addr,
+    pub bidder: String,
+    pub increment: Coin,
+    pub new_total: Coin,
+    /// Unset for the first bid of the auction
+    pub previous_leader: Option<String>,
+}
+
+impl From<BidEvent> for Event {
+    fn from(event: BidEvent) -> Self {
+        let mut result = Event::new(AUCTION_BID)
+            .add_attribute(ATTR_AUCTION_ID, event.auction_id)
+            .add_attribute(ATTR_BIDDER, event.bidder)
+            .add_attribute(ATTR_INCREMENT, event.increment.to_string())
+            .add_attribute(ATTR_NEW_TOTAL, event.new_total.to_string());
+        if let Some(previous_leader) = event.previous_leader {
+            result = result.add_attribute(ATTR_PREVIOUS_LEADER, previous_leader);
+        }
+        result
+    }
+}
+
+/// Bidding closed, emitted as `wasm-auction_close`. Winner attributes are left out when
+/// nobody bid.
+pub struct CloseEvent {
+    pub auction_id: Addr,
+    pub winner: Option<(String, Coin)>,
+}
+
+impl From<CloseEvent> for Event {
+    fn from(event: CloseEvent) -> Self {
+        let mut result = Event::new(AUCTION_CLOSE).add_attribute(ATTR_AUCTION_ID, event.auction_id);
+        if let Some((winner, bid)) = event.winner {
+            result = result
+                .add_attribute(ATTR_BIDDER, winner)
+                .add_attribute(ATTR_NEW_TOTAL, bid.to_string());
+        }
+        result
+    }
+}
+
+/// Funds were returned to a bidder, emitted as `wasm-auction_refund`. Refunds of remote
+/// bidders are released by their companion contract.
+pub struct RefundEvent {
+    pub auction_id: Addr,
+    pub bidder: String,
+    pub amount: Vec<Coin>,
+}
+
+impl From<RefundEvent> for Event {
+    fn from(event: RefundEvent) -> Self {
+        Event::new(AUCTION_REFUND)
+            .add_attribute(ATTR_AUCTION_ID, event.auction_id)
+            .add_attribute(ATTR_BIDDER, event.bidder)
+            .add_attribute(ATTR_AMOUNT, format_coins(&event.amount))
+    }
+}
+
+/// The escrowed winning bid was paid to the seller, emitted as `wasm-auction_settle`. Any share
+/// returned to the winner is reported by a separate refund event.
+pub struct SettleEvent {
+    pub auction_id: Addr,
+    pub bidder: String,
+    pub new_total: Coin,
+    /// Coins paid to the seller
+    pub amount: Vec<Coin>,
+    /// Coins paid to the commission recipient
+    pub commission: Vec<Coin>,
+}
+
+impl From<SettleEvent> for Event {
+    fn from(event: SettleEvent) -> Self {
+        Event::new(AUCTION_SETTLE)
+            .add_attribute(ATTR_AUCTION_ID, event.auction_id)
+            .add_attribute(ATTR_BIDDER, event.bidder)
+            .add_attribute(ATTR_NEW_TOTAL, event.new_total.to_string())
+            .add_attribute(ATTR_AMOUNT, format_coins(&event.amount))
+            .add_attribute(ATTR_COMMISSION, format_coins(&event.commission))
+    }
+}
@@ -1 +1,130 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, Coin, CosmosMsg, Empty, QuerierWrapper, QueryRequest, StdResult, WasmMsg,
+    WasmQuery,
+};
+use serde::de::DeserializeOwned;
 
+use crate::msg::{
+    AuctionStatusResponse, BidResponse, BidderStatusResponse, BlocklistResponse, ConfigResponse,
+    DeliveryEscrow, DepositsResponse, ExecuteMsg, HooksResponse, ItemResponse, QueryMsg,
+    SimulateBidResponse,
+};
+
+/// CwAuctionContract is a wrapper around Addr that provides helpers for contracts composing
+/// with an auction, like a DAO bidding from its treasury.
+#[cw_serde]
+pub struct CwAuctionContract(pub Addr);
+
+impl CwAuctionContract {
+    pub fn addr(&self) -> Addr {
+        self.0.clone()
+    }
+
+    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&msg.into())?;
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg,
+            funds,
+        }
+        .into())
+    }
+
+    pub fn query_request(&self, msg: &QueryMsg) -> StdResult<QueryRequest<Empty>> {
+        Ok(WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(msg)?,
+        }
+        .into())
+    }
+
+    fn query<T: DeserializeOwned>(&self, querier: &QuerierWrapper, msg: &QueryMsg) -> StdResult<T> {
+        querier.query(&self.query_request(msg)?)
+    }
+
+    pub fn status(&self, querier: &QuerierWrapper) -> StdResult<AuctionStatusResponse> {
+        self.query(querier, &QueryMsg::GetAuctionStatus {})
+    }
+
+    pub fn config(&self, querier: &QuerierWrapper) -> StdResult<ConfigResponse> {
+        self.query(querier, &QueryMsg::GetConfig {})
+    }
+
+    pub fn item(&self, querier: &QuerierWrapper) -> StdResult<ItemResponse> {
+        self.query(querier, &QueryMsg::GetItem {})
+    }
+
+    pub fn user_bid(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+    ) -> StdResult<BidResponse> {
+        self.query(
+            querier,
+            &QueryMsg::GetUserBid {
+                bidder: bidder.into(),
+            },
+        )
+    }
+
+    pub fn user_deposits(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+    ) -> StdResult<DepositsResponse> {
+        self.query(
+            querier,
+            &QueryMsg::GetUserDeposits {
+                bidder: bidder.into(),
+            },
+        )
+    }
+
+    pub fn blocklist(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BlocklistResponse> {
+        self.query(querier, &QueryMsg::GetBlocklist { start_after, limit })
+    }
+
+    pub fn hooks(&self, querier: &QuerierWrapper) -> StdResult<HooksResponse> {
+        self.query(querier, &QueryMsg::GetHooks {})
+    }
+
+    pub fn simulate_bid(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+        amount: Vec<Coin>,
+        proof: Option<Vec<String>>,
+    ) -> StdResult<SimulateBidResponse> {
+        self.query(
+            querier,
+            &QueryMsg::SimulateBid {
+                bidder: bidder.into(),
+                amount,
+                proof,
+            },
+        )
+    }
+
+    pub fn bidder_status(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+    ) -> StdResult<BidderStatusResponse> {
+        self.query(
+            querier,
+            &QueryMsg::GetBidderStatus {
+                bidder: bidder.into(),
+            },
+        )
+    }
+
+    pub fn escrow(&self, querier: &QuerierWrapper) -> StdResult<Option<DeliveryEscrow>> {
+        self.query(querier, &QueryMsg::GetEscrow {})
+    }
+}
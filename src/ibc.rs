@@ -0,0 +1,203 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_binary, to_binary, Coin, DepsMut, Empty, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcOrder, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, StdResult,
+    Storage,
+};
+
+use crate::contract::{
+    ensure_bidding_open, ensure_not_blocklisted, record_bid, remote_bid_key, verify_allowlist,
+};
+use crate::error::ContractError;
+use crate::msg::{AuctionPacketAck, AuctionPacketMsg, EscrowStatus};
+use crate::state::{RemoteBidder, ACTIVE, BIDS, ESCROW, IBC_CHANNELS, IBC_COUNTERPARTIES};
+
+/// Version companion contracts have to open channels with
+pub const IBC_VERSION: &str = "cw-auction-1";
+
+fn validate_channel(
+    storage: &dyn Storage,
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    let connection_id = &channel.connection_id;
+    let port_id = &channel.counterparty_endpoint.port_id;
+    if !IBC_COUNTERPARTIES.has(storage, (connection_id, port_id)) {
+        return Err(ContractError::UnknownIbcCounterparty {
+            connection_id: connection_id.clone(),
+            port_id: port_id.clone(),
+        });
+    }
+
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::OrderedChannel {});
+    }
+
+    for version in std::iter::once(channel.version.as_str()).chain(counterparty_version) {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidIbcVersion {
+                version: IBC_VERSION.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    validate_channel(deps.storage, msg.channel(), msg.counterparty_version())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(deps.storage, channel, msg.counterparty_version())?;
+
+    IBC_CHANNELS.save(deps.storage, channel.endpoint.channel_id.clone(), &Empty {})?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    IBC_CHANNELS.remove(deps.storage, channel.endpoint.channel_id.clone());
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+/// Failing bids are acknowledged with an error instead of aborting, so the companion contract
+/// can roll back and release the escrowed funds.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    match receive_packet(deps, env, msg.packet) {
+        Ok(response) => Ok(response),
+        Err(err) => Ok(IbcReceiveResponse::new()
+            .set_ack(to_binary(&AuctionPacketAck::Error(err.to_string()))?)
+            .add_attribute("action", "ibc_packet_receive")
+            .add_attribute("error", err.to_string())),
+    }
+}
+
+// state is only written once every check has passed, an error leaves no partial bid behind
+fn receive_packet(
+    deps: DepsMut,
+    env: Env,
+    packet: IbcPacket,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let (bidder, amount, proof) = match from_binary(&packet.data)? {
+        AuctionPacketMsg::Bid {
+            bidder,
+            amount,
+            proof,
+        } => (bidder, amount, proof),
+        _ => return Err(ContractError::UnexpectedPacket {}),
+    };
+
+    ensure_bidding_open(deps.storage)?;
+    verify_allowlist(deps.as_ref(), &bidder, proof)?;
+
+    let channel_id = packet.dest.channel_id;
+    let bid_key = remote_bid_key(&channel_id, &bidder);
+    ensure_not_blocklisted(deps.storage, &bidder)?;
+
+    let (response, new_bid) = record_bid(
+        deps,
+        &env,
+        &bid_key,
+        &[amount],
+        Some(RemoteBidder { channel_id, bidder }),
+    )?;
+
+    Ok(IbcReceiveResponse::new()
+        .set_ack(to_binary(&AuctionPacketAck::Result(to_binary(&new_bid)?))?)
+        .add_submessages(response.messages)
+        .add_attributes(response.attributes)
+        .add_events(response.events)
+        .add_attribute("action", "ibc_bid")
+        .add_attribute("bidder", bid_key)
+        .add_attribute("bid_amount", new_bid.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    match from_binary(&msg.acknowledgement.data)? {
+        AuctionPacketAck::Result(_) => Ok(IbcBasicResponse::new()
+            .add_attribute("action", "ibc_packet_ack")
+            .add_attribute("success", "true")),
+        AuctionPacketAck::Error(err) => Ok(on_packet_failure(deps, msg.original_packet)?
+            .add_attribute("action", "ibc_packet_ack")
+            .add_attribute("success", "false")
+            .add_attribute("error", err)),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(on_packet_failure(deps, msg.packet)?.add_attribute("action", "ibc_packet_timeout"))
+}
+
+/// An undelivered outbid notification means the companion still holds the escrow, so the bid is
+/// restored until the settlement notification releases it. An undelivered payout can be sent
+/// again with `RetryPayout`.
+fn on_packet_failure(deps: DepsMut, packet: IbcPacket) -> StdResult<IbcBasicResponse> {
+    let response = IbcBasicResponse::new();
+
+    match from_binary(&packet.data)? {
+        AuctionPacketMsg::Outbid { bidder, bid, .. } if ACTIVE.load(deps.storage)? => {
+            let bid_key = remote_bid_key(&packet.src.channel_id, &bidder);
+            // the bidder may have bid again in the meantime, both escrows are still held
+            BIDS.update(deps.storage, &bid_key, |current| -> StdResult<_> {
+                Ok(match current {
+                    Some(current) => {
+                        Coin::new((current.amount + bid.amount).u128(), bid.denom.clone())
+                    }
+                    None => bid.clone(),
+                })
+            })?;
+
+            Ok(response
+                .add_attribute("restored", bid_key)
+                .add_attribute("restored_amount", bid.to_string()))
+        }
+        AuctionPacketMsg::Payout { winner, .. } => {
+            let mut escrow = ESCROW.load(deps.storage)?;
+            escrow.status = EscrowStatus::PayoutFailed;
+            ESCROW.save(deps.storage, &escrow)?;
+
+            Ok(response.add_attribute("payout_failed", winner))
+        }
+        _ => Ok(response),
+    }
+}
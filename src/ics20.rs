@@ -0,0 +1,93 @@
+//! ICS-20 transfers sent as stargate messages. `IbcMsg::Transfer` can't carry a memo, which the
+//! ibc-hooks middleware needs to report the outcome of a transfer back to the sender contract.
+
+use cosmwasm_std::{Binary, Coin, CosmosMsg, Timestamp};
+
+const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+const TRANSFER_PORT: &str = "transfer";
+
+/// Transfer of `amount` from `sender` to `receiver` over `channel_id`. The ibc-hooks middleware
+/// calls `sudo` on `sender` once it was acknowledged or timed out.
+pub fn transfer_msg(
+    channel_id: &str,
+    sender: &str,
+    receiver: &str,
+    amount: &Coin,
+    timeout: Timestamp,
+) -> CosmosMsg {
+    let mut token = vec![];
+    encode_bytes(1, amount.denom.as_bytes(), &mut token);
+    encode_bytes(2, amount.amount.to_string().as_bytes(), &mut token);
+
+    let memo = format!(r#"{{"ibc_callback":"{}"}}"#, sender);
+
+    let mut value = vec![];
+    encode_bytes(1, TRANSFER_PORT.as_bytes(), &mut value);
+    encode_bytes(2, channel_id.as_bytes(), &mut value);
+    encode_bytes(3, &token, &mut value);
+    encode_bytes(4, sender.as_bytes(), &mut value);
+    encode_bytes(5, receiver.as_bytes(), &mut value);
+    encode_uint64(7, timeout.nanos(), &mut value);
+    encode_bytes(8, memo.as_bytes(), &mut value);
+
+    CosmosMsg::Stargate {
+        type_url: MSG_TRANSFER_TYPE_URL.to_string(),
+        value: Binary::from(value),
+    }
+}
+
+/// Packet sequence from the `MsgTransferResponse` a transfer submessage replied with.
+pub fn transfer_sequence(data: &[u8]) -> Option<u64> {
+    let mut data = data;
+    let mut sequence = None;
+
+    while !data.is_empty() {
+        let key = decode_varint(&mut data)?;
+        match (key >> 3, key & 0b111) {
+            (1, 0) => sequence = Some(decode_varint(&mut data)?),
+            (_, 0) => {
+                decode_varint(&mut data)?;
+            }
+            (_, 2) => {
+                let len = decode_varint(&mut data)? as usize;
+                data = data.get(len..)?;
+            }
+            _ => return None,
+        }
+    }
+
+    sequence
+}
+
+fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn encode_bytes(field: u64, bytes: &[u8], buf: &mut Vec<u8>) {
+    encode_varint((field << 3) | 2, buf);
+    encode_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+fn encode_uint64(field: u64, value: u64, buf: &mut Vec<u8>) {
+    encode_varint(field << 3, buf);
+    encode_varint(value, buf);
+}
+
+fn decode_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = data.split_first()?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
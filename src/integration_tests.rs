@@ -1,49 +1,358 @@
-use cosmwasm_std::Empty;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError,
+    StdResult,
+};
 use cw_multi_test::{Contract, ContractWrapper};
+use cw_storage_plus::{Item, Map};
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, query, reply};
+use crate::helpers::CwAuctionContract;
+use crate::msg::{
+    AuctionHookExecuteMsg, AuctionHookMsg, ExecuteMsg, OracleQueryMsg, PriceResponse,
+};
 
 fn auctioning_contract() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(execute, instantiate, query);
+    let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
 
     Box::new(contract)
 }
 
+const RECEIVED_HOOKS: Item<Vec<AuctionHookMsg>> = Item::new("received_hooks");
+const FAIL_HOOKS: Item<bool> = Item::new("fail_hooks");
+
+#[cw_serde]
+struct HookReceiverInstantiateMsg {
+    fail: bool,
+}
+
+/// Stores every auction hook it receives, or rejects them all when instantiated with `fail`
+fn hook_receiver_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: HookReceiverInstantiateMsg,
+    ) -> StdResult<Response> {
+        FAIL_HOOKS.save(deps.storage, &msg.fail)?;
+        RECEIVED_HOOKS.save(deps.storage, &vec![])?;
+        Ok(Response::new())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: AuctionHookExecuteMsg,
+    ) -> StdResult<Response> {
+        if FAIL_HOOKS.load(deps.storage)? {
+            return Err(StdError::generic_err("hook receiver failure"));
+        }
+
+        let AuctionHookExecuteMsg::AuctionHook(hook) = msg;
+        RECEIVED_HOOKS.update(deps.storage, |mut hooks| -> StdResult<_> {
+            hooks.push(hook);
+            Ok(hooks)
+        })?;
+        Ok(Response::new())
+    }
+
+    fn query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_binary(&RECEIVED_HOOKS.load(deps.storage)?)
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+const ORACLE_RATE: Item<Decimal> = Item::new("oracle_rate");
+
+/// Price oracle quoting the same rate for every denom
+fn oracle_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        rate: Decimal,
+    ) -> StdResult<Response> {
+        ORACLE_RATE.save(deps.storage, &rate)?;
+        Ok(Response::new())
+    }
+
+    fn execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn query(deps: Deps, _env: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+        match msg {
+            OracleQueryMsg::Price { .. } => to_binary(&PriceResponse {
+                rate: ORACLE_RATE.load(deps.storage)?,
+            }),
+        }
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[cw_serde]
+struct TreasuryBidMsg {
+    auction: String,
+    amount: Coin,
+}
+
+/// Treasury bidding on an auction through `CwAuctionContract`, as long as it is still running
+fn treasury_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: TreasuryBidMsg,
+    ) -> StdResult<Response> {
+        let auction = CwAuctionContract(deps.api.addr_validate(&msg.auction)?);
+        if !auction.status(&deps.querier)?.active {
+            return Err(StdError::generic_err("auction closed"));
+        }
+
+        let bid = ExecuteMsg::Bid {
+            bidder: None,
+            refund_address: None,
+            ibc_refund: None,
+            proof: None,
+        };
+        Ok(Response::new().add_message(auction.call(bid, vec![msg.amount])?))
+    }
+
+    fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_binary(&Empty {})
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+#[cw_serde]
+struct LegacyInstantiateMsg {
+    owner: Option<String>,
+    auction_item_title: String,
+    commission_percentage: Option<Decimal>,
+}
+
+#[cw_serde]
+enum LegacyExecuteMsg {
+    Bid {},
+}
+
+/// Storage layout of the first release: the owner and bidders are unvalidated strings, and the
+/// instantiating address gets an empty bid
+fn legacy_auctioning_contract() -> Box<dyn Contract<Empty>> {
+    const OWNER: Item<String> = Item::new("owner");
+    const AUCTION_ITEM_TITLE: Item<String> = Item::new("auction_item_title");
+    const COMMISSION_PERCENTAGE: Item<Decimal> = Item::new("commission_percentage");
+    const ACTIVE: Item<bool> = Item::new("active");
+    const BIDS: Map<String, Coin> = Map::new("bids");
+
+    fn bid_funds(info: &MessageInfo) -> Coin {
+        info.funds
+            .iter()
+            .find(|coin| coin.denom == "ubtc")
+            .cloned()
+            .unwrap_or_else(|| Coin::new(0, "ubtc"))
+    }
+
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: LegacyInstantiateMsg,
+    ) -> StdResult<Response> {
+        cw2::set_contract_version(deps.storage, "crates.io:cw-academy-auction", "0.1.0")?;
+        OWNER.save(
+            deps.storage,
+            &msg.owner.unwrap_or_else(|| info.sender.to_string()),
+        )?;
+        COMMISSION_PERCENTAGE.save(
+            deps.storage,
+            &msg.commission_percentage
+                .unwrap_or_else(|| Decimal::percent(5)),
+        )?;
+        AUCTION_ITEM_TITLE.save(deps.storage, &msg.auction_item_title)?;
+        ACTIVE.save(deps.storage, &true)?;
+        BIDS.save(deps.storage, info.sender.to_string(), &bid_funds(&info))?;
+
+        Ok(Response::new())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        _msg: LegacyExecuteMsg,
+    ) -> StdResult<Response> {
+        let funds = bid_funds(&info);
+        BIDS.update(
+            deps.storage,
+            info.sender.to_string(),
+            |bid| -> StdResult<_> {
+                let amount = bid.map(|bid| bid.amount).unwrap_or_default() + funds.amount;
+                Ok(Coin::new(amount.u128(), "ubtc"))
+            },
+        )?;
+
+        Ok(Response::new())
+    }
+
+    fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_binary(&Empty {})
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::integration_tests::auctioning_contract;
-    use crate::msg::{AuctionStatusResponse, BidResponse, InstantiateMsg};
+    use crate::helpers::CwAuctionContract;
+    use crate::integration_tests::{
+        auctioning_contract, hook_receiver_contract, legacy_auctioning_contract, oracle_contract,
+        treasury_contract, HookReceiverInstantiateMsg, LegacyExecuteMsg, LegacyInstantiateMsg,
+        TreasuryBidMsg,
+    };
+    use crate::interface::AuctionInterface;
+    use crate::msg::{
+        AuctionHookMsg, AuctionPacketAck, AuctionPacketMsg, AuctionStatusResponse, BidResponse,
+        BidderStatusResponse, BlocklistResponse, ConfigResponse, DeliveryEscrow, DenomRate,
+        DepositsResponse, EscrowStatus, ExecuteMsg, HooksResponse, IbcLifecycleComplete,
+        IbcRefundRoute, InstantiateMsg, ItemAttribute, ItemMetadata, ItemResponse, MigrateMsg,
+        OptionalSetting, PauseScope, PriceSource, SimulateBidResponse, StakingConfig,
+        SudoMsg as AuctionSudoMsg, HOOK_GAS_LIMIT,
+    };
     use crate::multitest::AuctionContract;
-    use cosmwasm_std::{coin, coins, Addr, Coin, Decimal, Uint128};
-    use cw_multi_test::App;
+    use crate::state::{BIDS, ESCROW, HOOKS};
+    use crate::{contract, ibc, ics20, ContractError};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel_connect_ack, mock_ibc_channel_open_init,
+        mock_ibc_packet_recv, mock_ibc_packet_timeout, mock_info,
+    };
+    use cosmwasm_std::{
+        coin, coins, from_binary, to_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Empty, IbcMsg,
+        IbcOrder, IbcTimeout, Reply, SubMsgResponse, SubMsgResult, Uint128, Validator,
+    };
+    use cw_multi_test::{App, AppResponse, BankSudo, Executor, StakingInfo, StakingSudo, SudoMsg};
+    use proptest::prelude::*;
+    use sha2::{Digest, Sha256};
+
+    /// Instantiate message of an auction with every optional setting left unset
+    fn default_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            owner: None,
+            auction_item_title: "Test Auction".to_string(),
+            item: None,
+            commission_percentage: None,
+            commission_recipient: None,
+            merkle_root: None,
+            max_bid_per_bidder: None,
+            guardian: None,
+            staking: None,
+            auto_refund_outbid: None,
+            retract_cooldown: None,
+            refund_keeper_tip: None,
+            accepted_denoms: None,
+            price_source: None,
+            arbiter: None,
+            delivery_timeout: None,
+        }
+    }
 
-    #[test]
-    fn instantiate_with_defaults() {
-        let sender = Addr::unchecked("sender");
+    /// App where every account starts with the given balance
+    fn mock_app(balances: &[(&Addr, Vec<Coin>)]) -> App {
+        App::new(|router, _api, storage| {
+            for (addr, balance) in balances {
+                router
+                    .bank
+                    .init_balance(storage, addr, balance.clone())
+                    .unwrap();
+            }
+        })
+    }
 
-        let mut app = App::new(|router, _api, storage| {
+    /// App with a single `validator` staking ubtc at 10% APR and a 60 second unbonding time
+    fn staking_app(balances: &[(&Addr, Vec<Coin>)]) -> App {
+        App::new(|router, api, storage| {
             router
-                .bank
-                .init_balance(storage, &sender, coins(100_000, "ubtc"))
+                .staking
+                .setup(
+                    storage,
+                    StakingInfo {
+                        bonded_denom: "ubtc".to_string(),
+                        unbonding_time: 60,
+                        apr: Decimal::percent(10),
+                    },
+                )
                 .unwrap();
-        });
 
-        let contract_id = app.store_code(auctioning_contract());
+            router
+                .staking
+                .add_validator(
+                    api,
+                    storage,
+                    &mock_env().block,
+                    Validator {
+                        address: "validator".to_string(),
+                        commission: Decimal::zero(),
+                        max_commission: Decimal::percent(100),
+                        max_change_rate: Decimal::percent(1),
+                    },
+                )
+                .unwrap();
 
-        let contract = AuctionContract::instantiate(
-            &mut app,
+            for (addr, balance) in balances {
+                router
+                    .bank
+                    .init_balance(storage, addr, balance.clone())
+                    .unwrap();
+            }
+        })
+    }
+
+    /// Stores the auction code and instantiates it from `sender`
+    #[track_caller]
+    fn instantiate_auction(app: &mut App, sender: &Addr, msg: &InstantiateMsg) -> AuctionContract {
+        let contract_id = app.store_code(auctioning_contract());
+        AuctionContract::instantiate(
+            app,
             contract_id,
-            &sender,
+            sender,
             None,
             "Test auction contract",
-            &coins(100_000, "ubtc"),
-            &InstantiateMsg {
-                owner: None,
-                auction_item_title: "Test Auction".to_string(),
-                commission_percentage: None,
-            },
+            &[],
+            msg,
         )
-        .unwrap();
+        .unwrap()
+    }
+
+    fn allowlist_leaf(addr: &Addr) -> [u8; 32] {
+        Sha256::digest(addr.as_bytes()).into()
+    }
+
+    fn allowlist_node(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+        let mut pair = [left, right];
+        pair.sort_unstable();
+        Sha256::digest(pair.concat()).into()
+    }
+
+    #[test]
+    fn instantiate_with_defaults() {
+        let sender = Addr::unchecked("sender");
+
+        let mut app = mock_app(&[(&sender, coins(100_000, "ubtc"))]);
+
+        let contract = instantiate_auction(&mut app, &sender, &default_instantiate_msg());
 
         let auction_status = contract.query_auction_status(&app).unwrap();
 
@@ -52,22 +361,21 @@ mod tests {
             AuctionStatusResponse {
                 owner: sender.to_string(),
                 active: true,
+                paused: false,
+                settlement_paused: false,
                 auction_item_title: "Test Auction".to_string(),
-                highest_bid: BidResponse {
-                    bidder: sender.to_string(),
-                    bid: Coin::new(100_000, "ubtc")
-                },
-                bidders_count: 1,
+                highest_bid: None,
+                bidders_count: 0,
                 commission_percentage: Decimal::new(Uint128::new(50_000_000_000_000_000)),
             }
         );
 
+        assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), &[]);
+
         assert_eq!(
-            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            app.wrap().query_all_balances(&sender).unwrap(),
             coins(100_000, "ubtc")
-        );
-
-        assert_eq!(app.wrap().query_all_balances(&sender).unwrap(), &[])
+        )
     }
 
     #[test]
@@ -75,29 +383,18 @@ mod tests {
         let sender = Addr::unchecked("sender");
         let auction_owner = Addr::unchecked("auction_owner");
 
-        let mut app = App::new(|router, _api, storage| {
-            router
-                .bank
-                .init_balance(storage, &sender, coins(100_000, "ubtc"))
-                .unwrap();
-        });
-
-        let contract_id = app.store_code(auctioning_contract());
+        let mut app = mock_app(&[(&sender, coins(100_000, "ubtc"))]);
 
-        let contract = AuctionContract::instantiate(
+        let contract = instantiate_auction(
             &mut app,
-            contract_id,
             &sender,
-            None,
-            "Test auction contract",
-            &coins(100_000, "ubtc"),
             &InstantiateMsg {
                 owner: Some(auction_owner.to_string()),
                 auction_item_title: "Test Auction 2".to_string(),
                 commission_percentage: Some(Decimal::new(Uint128::new(10_000_000_000_000_000))),
+                ..default_instantiate_msg()
             },
-        )
-        .unwrap();
+        );
 
         let auction_status = contract.query_auction_status(&app).unwrap();
 
@@ -106,22 +403,21 @@ mod tests {
             AuctionStatusResponse {
                 owner: auction_owner.to_string(),
                 active: true,
+                paused: false,
+                settlement_paused: false,
                 auction_item_title: "Test Auction 2".to_string(),
-                highest_bid: BidResponse {
-                    bidder: sender.to_string(),
-                    bid: Coin::new(100_000, "ubtc")
-                },
-                bidders_count: 1,
+                highest_bid: None,
+                bidders_count: 0,
                 commission_percentage: Decimal::new(Uint128::new(10_000_000_000_000_000)),
             }
         );
 
+        assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), &[]);
+
         assert_eq!(
-            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            app.wrap().query_all_balances(&sender).unwrap(),
             coins(100_000, "ubtc")
-        );
-
-        assert_eq!(app.wrap().query_all_balances(&sender).unwrap(), &[])
+        )
     }
 
     #[test]
@@ -130,34 +426,16 @@ mod tests {
         let bidder = Addr::unchecked("bidder");
         let non_bidder = Addr::unchecked("non_bidder");
 
-        let mut app = App::new(|router, _api, storage| {
-            router
-                .bank
-                .init_balance(storage, &sender, coins(100_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder, coins(150_000, "ubtc"))
-                .unwrap();
-        });
+        let mut app = mock_app(&[
+            (&sender, coins(100_000, "ubtc")),
+            (&bidder, coins(150_000, "ubtc")),
+        ]);
 
-        let contract_id = app.store_code(auctioning_contract());
+        let contract = instantiate_auction(&mut app, &sender, &default_instantiate_msg());
 
-        let contract = AuctionContract::instantiate(
-            &mut app,
-            contract_id,
-            &sender,
-            None,
-            "Test auction contract",
-            &coins(100_000, "ubtc"),
-            &InstantiateMsg {
-                owner: None,
-                auction_item_title: "Test Auction".to_string(),
-                commission_percentage: None,
-            },
-        )
-        .unwrap();
+        contract
+            .bid(&mut app, &sender, &coins(100_000, "ubtc"))
+            .unwrap();
 
         let _ = contract.bid(&mut app, &bidder, &coins(150_000, "ubtc"));
 
@@ -204,39 +482,17 @@ mod tests {
         let bidder = Addr::unchecked("bidder");
         let bidder_two = Addr::unchecked("bidder_two");
 
-        let mut app = App::new(|router, _api, storage| {
-            router
-                .bank
-                .init_balance(storage, &sender, coins(100_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder, coins(150_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder_two, coins(300_000, "ubtc"))
-                .unwrap();
-        });
+        let mut app = mock_app(&[
+            (&sender, coins(100_000, "ubtc")),
+            (&bidder, coins(150_000, "ubtc")),
+            (&bidder_two, coins(300_000, "ubtc")),
+        ]);
 
-        let contract_id = app.store_code(auctioning_contract());
+        let contract = instantiate_auction(&mut app, &sender, &default_instantiate_msg());
 
-        let contract = AuctionContract::instantiate(
-            &mut app,
-            contract_id,
-            &sender,
-            None,
-            "Test auction contract",
-            &coins(100_000, "ubtc"),
-            &InstantiateMsg {
-                owner: None,
-                auction_item_title: "Test Auction".to_string(),
-                commission_percentage: None,
-            },
-        )
-        .unwrap();
+        contract
+            .bid(&mut app, &sender, &coins(100_000, "ubtc"))
+            .unwrap();
 
         let AuctionStatusResponse {
             highest_bid: initial_highest_bid,
@@ -247,10 +503,10 @@ mod tests {
         assert_eq!(initial_bidders_count, 1);
         assert_eq!(
             initial_highest_bid,
-            BidResponse {
+            Some(BidResponse {
                 bidder: sender.to_string(),
                 bid: Coin::new(100_000, "ubtc")
-            }
+            })
         );
 
         let _ = contract.bid(&mut app, &bidder, &coins(150_000, "ubtc"));
@@ -264,10 +520,10 @@ mod tests {
         assert_eq!(new_bidders_count, 2);
         assert_eq!(
             new_highest_bid,
-            BidResponse {
+            Some(BidResponse {
                 bidder: bidder.to_string(),
                 bid: Coin::new(150_000, "ubtc")
-            }
+            })
         );
 
         let _ = contract.bid(&mut app, &bidder_two, &coins(250_000, "ubtc"));
@@ -281,10 +537,10 @@ mod tests {
         assert_eq!(final_bidders_count, 3);
         assert_eq!(
             final_highest_bid,
-            BidResponse {
+            Some(BidResponse {
                 bidder: bidder_two.to_string(),
                 bid: Coin::new(250_000, "ubtc")
-            }
+            })
         );
 
         assert_eq!(
@@ -304,34 +560,16 @@ mod tests {
         let sender = Addr::unchecked("sender");
         let bidder = Addr::unchecked("bidder");
 
-        let mut app = App::new(|router, _api, storage| {
-            router
-                .bank
-                .init_balance(storage, &sender, coins(100_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder, coins(100_000, "ubtc"))
-                .unwrap();
-        });
+        let mut app = mock_app(&[
+            (&sender, coins(100_000, "ubtc")),
+            (&bidder, coins(100_000, "ubtc")),
+        ]);
 
-        let contract_id = app.store_code(auctioning_contract());
+        let contract = instantiate_auction(&mut app, &sender, &default_instantiate_msg());
 
-        let contract = AuctionContract::instantiate(
-            &mut app,
-            contract_id,
-            &sender,
-            None,
-            "Test auction contract",
-            &coins(100_000, "ubtc"),
-            &InstantiateMsg {
-                owner: None,
-                auction_item_title: "Test Auction".to_string(),
-                commission_percentage: None,
-            },
-        )
-        .unwrap();
+        contract
+            .bid(&mut app, &sender, &coins(100_000, "ubtc"))
+            .unwrap();
 
         let _ = contract.bid(&mut app, &bidder, &coins(100_000, "ubtc"));
 
@@ -344,10 +582,10 @@ mod tests {
         assert_eq!(new_bidders_count, 1);
         assert_eq!(
             new_highest_bid,
-            BidResponse {
+            Some(BidResponse {
                 bidder: sender.to_string(),
                 bid: Coin::new(100_000, "ubtc")
-            }
+            })
         );
 
         assert_eq!(
@@ -366,29 +604,13 @@ mod tests {
         let sender = Addr::unchecked("sender");
         let bidder = Addr::unchecked("bidder");
 
-        let mut app = App::new(|router, _api, storage| {
-            router
-                .bank
-                .init_balance(storage, &sender, coins(100_000, "ubtc"))
-                .unwrap();
-        });
+        let mut app = mock_app(&[(&sender, coins(100_000, "ubtc"))]);
 
-        let contract_id = app.store_code(auctioning_contract());
+        let contract = instantiate_auction(&mut app, &sender, &default_instantiate_msg());
 
-        let contract = AuctionContract::instantiate(
-            &mut app,
-            contract_id,
-            &sender,
-            None,
-            "Test auction contract",
-            &coins(100_000, "ubtc"),
-            &InstantiateMsg {
-                owner: None,
-                auction_item_title: "Test Auction".to_string(),
-                commission_percentage: None,
-            },
-        )
-        .unwrap();
+        contract
+            .bid(&mut app, &sender, &coins(100_000, "ubtc"))
+            .unwrap();
 
         let _ = contract.close_bidding(&mut app, &bidder.clone());
 
@@ -396,7 +618,7 @@ mod tests {
             active: auction_active,
             ..
         } = contract.query_auction_status(&app).unwrap();
-        assert_eq!(auction_active, true);
+        assert!(auction_active);
 
         let _ = contract.close_bidding(&mut app, &sender);
 
@@ -404,48 +626,26 @@ mod tests {
             active: auction_active,
             ..
         } = contract.query_auction_status(&app).unwrap();
-        assert_eq!(auction_active, false);
+        assert!(!auction_active);
     }
 
     #[test]
-    fn cannot_retract_funds_while_active() {
+    fn leader_cannot_retract_funds_while_active() {
         let owner = Addr::unchecked("sender");
         let bidder = Addr::unchecked("bidder");
         let bidder_two = Addr::unchecked("bidder_two");
 
-        let mut app = App::new(|router, _api, storage| {
-            router
-                .bank
-                .init_balance(storage, &owner, coins(100_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder, coins(150_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder_two, coins(200_000, "ubtc"))
-                .unwrap();
-        });
+        let mut app = mock_app(&[
+            (&owner, coins(100_000, "ubtc")),
+            (&bidder, coins(150_000, "ubtc")),
+            (&bidder_two, coins(200_000, "ubtc")),
+        ]);
 
-        let contract_id = app.store_code(auctioning_contract());
+        let contract = instantiate_auction(&mut app, &owner, &default_instantiate_msg());
 
-        let contract = AuctionContract::instantiate(
-            &mut app,
-            contract_id,
-            &owner,
-            None,
-            "Test auction contract",
-            &coins(100_000, "ubtc"),
-            &InstantiateMsg {
-                owner: None,
-                auction_item_title: "Test Auction".to_string(),
-                commission_percentage: None,
-            },
-        )
-        .unwrap();
+        contract
+            .bid(&mut app, &owner, &coins(100_000, "ubtc"))
+            .unwrap();
 
         let _ = contract.bid(&mut app, &bidder, &coins(150_000, "ubtc"));
 
@@ -454,7 +654,7 @@ mod tests {
             coins(250_000, "ubtc")
         );
 
-        let _ = contract.retract_funds(&mut app, &owner, None);
+        let _ = contract.retract_funds(&mut app, &bidder, None);
 
         assert_eq!(
             app.wrap().query_all_balances(contract.addr()).unwrap(),
@@ -474,7 +674,7 @@ mod tests {
             active: auction_active,
             ..
         } = contract.query_auction_status(&app).unwrap();
-        assert_eq!(auction_active, false);
+        assert!(!auction_active);
     }
 
     #[test]
@@ -483,39 +683,17 @@ mod tests {
         let bidder = Addr::unchecked("bidder");
         let bidder_two = Addr::unchecked("bidder_two");
 
-        let mut app = App::new(|router, _api, storage| {
-            router
-                .bank
-                .init_balance(storage, &owner, coins(100_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder, coins(150_000, "ubtc"))
-                .unwrap();
-
-            router
-                .bank
-                .init_balance(storage, &bidder_two, coins(200_000, "ubtc"))
-                .unwrap();
-        });
+        let mut app = mock_app(&[
+            (&owner, coins(100_000, "ubtc")),
+            (&bidder, coins(150_000, "ubtc")),
+            (&bidder_two, coins(200_000, "ubtc")),
+        ]);
 
-        let contract_id = app.store_code(auctioning_contract());
+        let contract = instantiate_auction(&mut app, &owner, &default_instantiate_msg());
 
-        let contract = AuctionContract::instantiate(
-            &mut app,
-            contract_id,
-            &owner,
-            None,
-            "Test auction contract",
-            &coins(100_000, "ubtc"),
-            &InstantiateMsg {
-                owner: None,
-                auction_item_title: "Test Auction".to_string(),
-                commission_percentage: None,
-            },
-        )
-        .unwrap();
+        contract
+            .bid(&mut app, &owner, &coins(100_000, "ubtc"))
+            .unwrap();
 
         let _ = contract.bid(&mut app, &bidder, &coins(150_000, "ubtc"));
 
@@ -540,4 +718,3224 @@ mod tests {
             coins(150_000, "ubtc")
         );
     }
+
+    #[test]
+    fn only_allowlisted_bidders_can_bid() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+        let outsider = Addr::unchecked("outsider");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(150_000, "ubtc")),
+            (&outsider, coins(200_000, "ubtc")),
+        ]);
+
+        let contract_id = app.store_code(auctioning_contract());
+
+        let merkle_root = allowlist_node(allowlist_leaf(&bidder), allowlist_leaf(&bidder_two));
+
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &InstantiateMsg {
+                merkle_root: Some(hex::encode(merkle_root)),
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        let err = contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+
+        contract
+            .bid_with_proof(
+                &mut app,
+                &bidder,
+                &coins(100_000, "ubtc"),
+                vec![hex::encode(allowlist_leaf(&bidder_two))],
+            )
+            .unwrap();
+
+        let err = contract
+            .bid_with_proof(
+                &mut app,
+                &outsider,
+                &coins(200_000, "ubtc"),
+                vec![hex::encode(allowlist_leaf(&bidder_two))],
+            )
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+
+        let AuctionStatusResponse { highest_bid, .. } =
+            contract.query_auction_status(&app).unwrap();
+        assert_eq!(
+            highest_bid,
+            Some(BidResponse {
+                bidder: bidder.to_string(),
+                bid: Coin::new(100_000, "ubtc")
+            })
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&outsider).unwrap(),
+            coins(200_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn only_owner_can_update_merkle_root() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let outsider = Addr::unchecked("outsider");
+
+        let mut app = mock_app(&[(&outsider, coins(100_000, "ubtc"))]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                merkle_root: Some(hex::encode(allowlist_leaf(&bidder))),
+                ..default_instantiate_msg()
+            },
+        );
+
+        let err = contract
+            .update_merkle_root(&mut app, &outsider, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        let err = contract
+            .update_merkle_root(&mut app, &owner, Some("not a hash".to_string()))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Hex(_)));
+
+        contract
+            .update_merkle_root(
+                &mut app,
+                &owner,
+                Some(hex::encode(allowlist_leaf(&outsider))),
+            )
+            .unwrap();
+
+        contract
+            .bid_with_proof(&mut app, &outsider, &coins(100_000, "ubtc"), vec![])
+            .unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(100_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn blocklisted_bidders_cannot_bid() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bad_actor = Addr::unchecked("bad_actor");
+        let other_bad_actor = Addr::unchecked("other_bad_actor");
+
+        let mut app = mock_app(&[(&bad_actor, coins(200_000, "ubtc"))]);
+
+        let contract = instantiate_auction(&mut app, &owner, &default_instantiate_msg());
+
+        let err = contract
+            .add_to_blocklist(&mut app, &bidder, vec![bad_actor.to_string()])
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        contract
+            .add_to_blocklist(
+                &mut app,
+                &owner,
+                vec![bad_actor.to_string(), other_bad_actor.to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.query_blocklist(&app, None, None).unwrap(),
+            BlocklistResponse {
+                addresses: vec![bad_actor.to_string(), other_bad_actor.to_string()]
+            }
+        );
+        assert_eq!(
+            contract
+                .query_blocklist(&app, Some(bad_actor.to_string()), Some(1))
+                .unwrap(),
+            BlocklistResponse {
+                addresses: vec![other_bad_actor.to_string()]
+            }
+        );
+
+        let err = contract
+            .bid(&mut app, &bad_actor, &coins(100_000, "ubtc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Blocklisted { .. }));
+
+        contract
+            .remove_from_blocklist(&mut app, &owner, vec![bad_actor.to_string()])
+            .unwrap();
+
+        contract
+            .bid(&mut app, &bad_actor, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(100_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn bids_cannot_exceed_bidder_cap() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[(&bidder, coins(300_000, "ubtc"))]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                max_bid_per_bidder: Some(Uint128::new(150_000)),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        let err = contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::BidCapExceeded { .. }));
+
+        contract
+            .bid(&mut app, &bidder, &coins(50_000, "ubtc"))
+            .unwrap();
+
+        assert_eq!(
+            contract
+                .query_user_bid(&app, bidder.to_string())
+                .unwrap()
+                .bid,
+            coin(150_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(150_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn guardian_can_pause_bidding_and_closing() {
+        let owner = Addr::unchecked("sender");
+        let guardian = Addr::unchecked("guardian");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(100_000, "ubtc")),
+            (&bidder_two, coins(200_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                guardian: Some(guardian.to_string()),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        let err = contract.pause(&mut app, &owner, None).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        contract.pause(&mut app, &guardian, None).unwrap();
+        assert!(contract.query_auction_status(&app).unwrap().paused);
+
+        let err = contract
+            .bid(&mut app, &bidder_two, &coins(200_000, "ubtc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::AuctionPaused {}));
+
+        let err = contract.close_bidding(&mut app, &owner).unwrap_err();
+        assert!(matches!(err, ContractError::SettlementPaused {}));
+
+        contract.unpause(&mut app, &guardian, None).unwrap();
+
+        contract
+            .bid(&mut app, &bidder_two, &coins(200_000, "ubtc"))
+            .unwrap();
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        contract.pause(&mut app, &guardian, None).unwrap();
+
+        contract.retract_funds(&mut app, &bidder, None).unwrap();
+
+        let AuctionStatusResponse { active, paused, .. } =
+            contract.query_auction_status(&app).unwrap();
+        assert!(!active);
+        assert!(paused);
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(100_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn bidding_and_settlement_pause_independently() {
+        let owner = Addr::unchecked("sender");
+        let guardian = Addr::unchecked("guardian");
+        let new_guardian = Addr::unchecked("new_guardian");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[(&bidder, coins(200_000, "ubtc"))]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                guardian: Some(guardian.to_string()),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        contract
+            .pause(&mut app, &guardian, Some(PauseScope::Settlement))
+            .unwrap();
+
+        // bids keep coming in while the winner can't be picked
+        contract
+            .bid(&mut app, &bidder, &coins(50_000, "ubtc"))
+            .unwrap();
+        let err = contract.close_bidding(&mut app, &owner).unwrap_err();
+        assert!(matches!(err, ContractError::SettlementPaused {}));
+
+        // the owner replaces the guardian even though bidding started
+        let err = contract
+            .update_guardian(&mut app, &guardian, &new_guardian)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+        contract
+            .update_guardian(&mut app, &owner, &new_guardian)
+            .unwrap();
+
+        let err = contract
+            .unpause(&mut app, &guardian, Some(PauseScope::Settlement))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+        contract
+            .unpause(&mut app, &new_guardian, Some(PauseScope::Settlement))
+            .unwrap();
+        contract
+            .pause(&mut app, &new_guardian, Some(PauseScope::Bidding))
+            .unwrap();
+
+        let AuctionStatusResponse {
+            paused,
+            settlement_paused,
+            ..
+        } = contract.query_auction_status(&app).unwrap();
+        assert!(paused);
+        assert!(!settlement_paused);
+
+        let err = contract
+            .bid(&mut app, &bidder, &coins(50_000, "ubtc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::AuctionPaused {}));
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        assert_eq!(
+            contract.query_config(&app).unwrap().guardian,
+            new_guardian.to_string()
+        );
+    }
+
+    #[test]
+    fn staked_bids_earn_rewards_pro_rata() {
+        const YEAR: u64 = 60 * 60 * 24 * 365;
+
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = staking_app(&[
+            (&bidder, coins(1_000_000, "ubtc")),
+            (&bidder_two, coins(2_000_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                staking: Some(StakingConfig {
+                    validator: "validator".to_string(),
+                }),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(1_000_000, "ubtc"))
+            .unwrap();
+        assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), &[]);
+
+        app.update_block(|block| block.time = block.time.plus_seconds(YEAR / 2));
+
+        contract
+            .bid(&mut app, &bidder_two, &coins(2_000_000, "ubtc"))
+            .unwrap();
+
+        app.update_block(|block| block.time = block.time.plus_seconds(YEAR / 2));
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        // 50_000 rewards for the first half year and 150_000 for the second, split evenly by weight
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(200_000, "ubtc")
+        );
+
+        let err = contract.retract_funds(&mut app, &bidder, None).unwrap_err();
+        assert!(matches!(err, ContractError::FundsUnbonding { .. }));
+
+        app.update_block(|block| block.time = block.time.plus_seconds(60));
+        app.sudo(SudoMsg::Staking(StakingSudo::ProcessQueue {}))
+            .unwrap();
+
+        contract.retract_funds(&mut app, &bidder, None).unwrap();
+        contract.retract_funds(&mut app, &bidder_two, None).unwrap();
+
+        let err = contract
+            .retract_funds(&mut app, &bidder_two, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(1_100_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder_two).unwrap(),
+            coins(100_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(2_000_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn slashed_stake_is_shared_by_all_bidders() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = staking_app(&[
+            (&bidder, coins(1_000_000, "ubtc")),
+            (&bidder_two, coins(2_000_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                staking: Some(StakingConfig {
+                    validator: "validator".to_string(),
+                }),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(1_000_000, "ubtc"))
+            .unwrap();
+        contract
+            .bid(&mut app, &bidder_two, &coins(2_000_000, "ubtc"))
+            .unwrap();
+
+        app.sudo(SudoMsg::Staking(StakingSudo::Slash {
+            validator: "validator".to_string(),
+            percentage: Decimal::percent(10),
+        }))
+        .unwrap();
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        // the stake only counts as back once it reached the contract
+        app.update_block(|block| block.time = block.time.plus_seconds(600));
+        let err = contract.retract_funds(&mut app, &bidder, None).unwrap_err();
+        assert!(matches!(err, ContractError::FundsUnbonding {}));
+
+        app.sudo(SudoMsg::Staking(StakingSudo::ProcessQueue {}))
+            .unwrap();
+
+        contract.retract_funds(&mut app, &bidder, None).unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(900_000, "ubtc")
+        );
+
+        let escrow = contract.query_escrow(&app).unwrap().unwrap();
+        assert_eq!(escrow.funds, coins(1_800_000, "ubtc"));
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(1_800_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn hooks_are_notified_of_bids_and_close() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(100_000, "ubtc")),
+            (&bidder_two, coins(200_000, "ubtc")),
+        ]);
+
+        let contract_id = app.store_code(auctioning_contract());
+        let receiver_id = app.store_code(hook_receiver_contract());
+
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &default_instantiate_msg(),
+        )
+        .unwrap();
+
+        let receiver = app
+            .instantiate_contract(
+                receiver_id,
+                owner.clone(),
+                &HookReceiverInstantiateMsg { fail: false },
+                &[],
+                "Hook receiver",
+                None,
+            )
+            .unwrap();
+        let failing_receiver = app
+            .instantiate_contract(
+                receiver_id,
+                owner.clone(),
+                &HookReceiverInstantiateMsg { fail: true },
+                &[],
+                "Failing hook receiver",
+                None,
+            )
+            .unwrap();
+
+        let err = contract.add_hook(&mut app, &bidder, &receiver).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        contract.add_hook(&mut app, &owner, &receiver).unwrap();
+        contract
+            .add_hook(&mut app, &owner, &failing_receiver)
+            .unwrap();
+
+        assert_eq!(
+            contract.query_hooks(&app).unwrap(),
+            HooksResponse {
+                hooks: vec![receiver.to_string(), failing_receiver.to_string()]
+            }
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+        contract
+            .bid(&mut app, &bidder_two, &coins(200_000, "ubtc"))
+            .unwrap();
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        let received: Vec<AuctionHookMsg> =
+            app.wrap().query_wasm_smart(&receiver, &Empty {}).unwrap();
+        assert_eq!(
+            received,
+            vec![
+                AuctionHookMsg::NewBid {
+                    bidder: bidder.to_string(),
+                    bid: coin(100_000, "ubtc")
+                },
+                AuctionHookMsg::NewBid {
+                    bidder: bidder_two.to_string(),
+                    bid: coin(200_000, "ubtc")
+                },
+                AuctionHookMsg::Outbid {
+                    bidder: bidder.to_string(),
+                    bid: coin(100_000, "ubtc"),
+                    outbid_by: bidder_two.to_string()
+                },
+                AuctionHookMsg::Closed {
+                    highest_bid: Some(BidResponse {
+                        bidder: bidder_two.to_string(),
+                        bid: coin(200_000, "ubtc")
+                    })
+                },
+            ]
+        );
+
+        contract.remove_hook(&mut app, &owner, &receiver).unwrap();
+        assert_eq!(
+            contract.query_hooks(&app).unwrap(),
+            HooksResponse {
+                hooks: vec![failing_receiver.to_string()]
+            }
+        );
+    }
+
+    #[test]
+    fn outbid_bidders_are_refunded_automatically() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(300_000, "ubtc")),
+            (&bidder_two, coins(200_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auto_refund_outbid: Some(true),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+        contract
+            .bid(&mut app, &bidder_two, &coins(200_000, "ubtc"))
+            .unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(300_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(200_000, "ubtc")
+        );
+
+        let AuctionStatusResponse {
+            highest_bid,
+            bidders_count,
+            ..
+        } = contract.query_auction_status(&app).unwrap();
+        assert_eq!(bidders_count, 1);
+        assert_eq!(
+            highest_bid,
+            Some(BidResponse {
+                bidder: bidder_two.to_string(),
+                bid: coin(200_000, "ubtc")
+            })
+        );
+
+        // the refunded bid no longer counts towards a new one
+        let err = contract
+            .bid(&mut app, &bidder, &coins(150_000, "ubtc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow { .. }));
+
+        contract
+            .bid(&mut app, &bidder, &coins(250_000, "ubtc"))
+            .unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder_two).unwrap(),
+            coins(200_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(250_000, "ubtc")
+        );
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        let err = contract
+            .retract_funds(&mut app, &bidder_two, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+    }
+
+    #[test]
+    fn outbid_bidders_can_withdraw_while_active() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(100_000, "ubtc")),
+            (&bidder_two, coins(200_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                retract_cooldown: Some(60),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+        contract
+            .bid(&mut app, &bidder_two, &coins(200_000, "ubtc"))
+            .unwrap();
+
+        let err = contract
+            .retract_amount(&mut app, &bidder, 40_000)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::RetractCooldown { .. }));
+
+        app.update_block(|block| block.time = block.time.plus_seconds(60));
+
+        let err = contract
+            .retract_funds(&mut app, &bidder_two, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        let err = contract
+            .retract_amount(&mut app, &bidder, 150_000)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::WithdrawalTooLarge { .. }));
+
+        contract.retract_amount(&mut app, &bidder, 40_000).unwrap();
+
+        assert_eq!(
+            contract
+                .query_user_bid(&app, bidder.to_string())
+                .unwrap()
+                .bid,
+            coin(60_000, "ubtc")
+        );
+        assert_eq!(
+            contract.query_auction_status(&app).unwrap().bidders_count,
+            2
+        );
+
+        contract.retract_funds(&mut app, &bidder, None).unwrap();
+
+        assert_eq!(
+            contract
+                .query_user_bid(&app, bidder.to_string())
+                .unwrap()
+                .bid,
+            coin(0, "ubtc")
+        );
+        assert_eq!(
+            contract.query_auction_status(&app).unwrap().bidders_count,
+            1
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(100_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(200_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn fees_are_validated() {
+        let owner = Addr::unchecked("sender");
+
+        let mut app = App::default();
+        let contract_id = app.store_code(auctioning_contract());
+
+        for refund_keeper_tip in [Decimal::percent(6), Decimal::one(), Decimal::percent(150)] {
+            let err = app
+                .instantiate_contract(
+                    contract_id,
+                    owner.clone(),
+                    &InstantiateMsg {
+                        refund_keeper_tip: Some(refund_keeper_tip),
+                        ..default_instantiate_msg()
+                    },
+                    &[],
+                    "Test auction contract",
+                    None,
+                )
+                .unwrap_err()
+                .downcast::<ContractError>()
+                .unwrap();
+            assert!(matches!(
+                err,
+                ContractError::RefundKeeperTipTooHigh { max } if max == Decimal::percent(5)
+            ));
+        }
+
+        let err = app
+            .instantiate_contract(
+                contract_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    commission_percentage: Some(Decimal::percent(101)),
+                    ..default_instantiate_msg()
+                },
+                &[],
+                "Test auction contract",
+                None,
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::InvalidCommission {}));
+
+        let contract = instantiate_auction(&mut app, &owner, &default_instantiate_msg());
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contract.addr().clone(),
+                &ExecuteMsg::UpdateConfig {
+                    auction_item_title: None,
+                    commission_percentage: Some(Decimal::percent(200)),
+                    commission_recipient: None,
+                    max_bid_per_bidder: None,
+                    guardian: None,
+                    retract_cooldown: None,
+                    refund_keeper_tip: None,
+                    arbiter: None,
+                    delivery_timeout: None,
+                    clear: None,
+                },
+                &[],
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::InvalidCommission {}));
+
+        let update_config = |refund_keeper_tip| ExecuteMsg::UpdateConfig {
+            auction_item_title: None,
+            commission_percentage: None,
+            commission_recipient: None,
+            max_bid_per_bidder: None,
+            guardian: None,
+            retract_cooldown: None,
+            refund_keeper_tip: Some(refund_keeper_tip),
+            arbiter: None,
+            delivery_timeout: None,
+            clear: None,
+        };
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contract.addr().clone(),
+                &update_config(Decimal::one()),
+                &[],
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::RefundKeeperTipTooHigh { .. }));
+
+        app.execute_contract(
+            owner.clone(),
+            contract.addr().clone(),
+            &update_config(Decimal::percent(5)),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            contract.query_config(&app).unwrap().refund_keeper_tip,
+            Decimal::percent(5)
+        );
+    }
+
+    #[test]
+    fn keepers_can_push_refunds_in_batches() {
+        let owner = Addr::unchecked("sender");
+        let keeper = Addr::unchecked("keeper");
+        let bidder_a = Addr::unchecked("bidder_a");
+        let bidder_b = Addr::unchecked("bidder_b");
+        let bidder_c = Addr::unchecked("bidder_c");
+        let winner = Addr::unchecked("winner");
+
+        let mut app = mock_app(&[
+            (&bidder_a, coins(100_000, "ubtc")),
+            (&bidder_b, coins(110_000, "ubtc")),
+            (&bidder_c, coins(120_000, "ubtc")),
+            (&winner, coins(200_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                refund_keeper_tip: Some(Decimal::percent(1)),
+                ..default_instantiate_msg()
+            },
+        );
+
+        for (bidder, amount) in [
+            (&bidder_a, 100_000),
+            (&bidder_b, 110_000),
+            (&bidder_c, 120_000),
+            (&winner, 200_000),
+        ] {
+            contract
+                .bid(&mut app, bidder, &coins(amount, "ubtc"))
+                .unwrap();
+        }
+
+        let err = contract
+            .process_refunds(&mut app, &keeper, Some(2))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::AuctionActive {}));
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        contract
+            .process_refunds(&mut app, &keeper, Some(2))
+            .unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder_a).unwrap(),
+            coins(99_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder_b).unwrap(),
+            coins(108_900, "ubtc")
+        );
+        assert_eq!(app.wrap().query_all_balances(&bidder_c).unwrap(), &[]);
+
+        contract
+            .process_refunds(&mut app, &keeper, Some(2))
+            .unwrap();
+        contract
+            .process_refunds(&mut app, &keeper, Some(2))
+            .unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder_c).unwrap(),
+            coins(118_800, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&keeper).unwrap(),
+            coins(3_300, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(200_000, "ubtc")
+        );
+
+        let err = contract
+            .retract_funds(&mut app, &bidder_a, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+    }
+
+    #[test]
+    fn custodians_can_bid_on_behalf_of_users() {
+        let owner = Addr::unchecked("sender");
+        let custodian = Addr::unchecked("custodian");
+        let user = Addr::unchecked("user");
+        let user_vault = Addr::unchecked("user_vault");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[
+            (&custodian, coins(100_000, "ubtc")),
+            (&bidder, coins(200_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(&mut app, &owner, &default_instantiate_msg());
+
+        contract
+            .bid_on_behalf(
+                &mut app,
+                &custodian,
+                &user,
+                Some(user_vault.to_string()),
+                &coins(100_000, "ubtc"),
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.query_user_bid(&app, user.to_string()).unwrap().bid,
+            coin(100_000, "ubtc")
+        );
+        assert_eq!(
+            contract
+                .query_user_bid(&app, custodian.to_string())
+                .unwrap()
+                .bid,
+            coin(0, "ubtc")
+        );
+        assert_eq!(
+            contract.query_auction_status(&app).unwrap().highest_bid,
+            Some(BidResponse {
+                bidder: user.to_string(),
+                bid: coin(100_000, "ubtc")
+            })
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(200_000, "ubtc"))
+            .unwrap();
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        let err = contract
+            .retract_funds(&mut app, &custodian, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        contract.retract_funds(&mut app, &user, None).unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&user_vault).unwrap(),
+            coins(100_000, "ubtc")
+        );
+        assert_eq!(app.wrap().query_all_balances(&user).unwrap(), &[]);
+        assert_eq!(app.wrap().query_all_balances(&custodian).unwrap(), &[]);
+    }
+
+    #[test]
+    fn third_parties_cannot_redirect_refunds() {
+        let owner = Addr::unchecked("sender");
+        let custodian = Addr::unchecked("custodian");
+        let attacker = Addr::unchecked("attacker");
+        let user = Addr::unchecked("user");
+        let user_vault = Addr::unchecked("user_vault");
+        let user_wallet = Addr::unchecked("user_wallet");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[
+            (&custodian, coins(100_000, "ubtc")),
+            (&attacker, coins(1, "ubtc")),
+            (&user, coins(1, "ubtc")),
+            (&bidder, coins(200_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(&mut app, &owner, &default_instantiate_msg());
+
+        contract
+            .bid_on_behalf(
+                &mut app,
+                &custodian,
+                &user,
+                Some(user_vault.to_string()),
+                &coins(100_000, "ubtc"),
+            )
+            .unwrap();
+
+        let err = contract
+            .bid_on_behalf(
+                &mut app,
+                &attacker,
+                &user,
+                Some(attacker.to_string()),
+                &coins(1, "ubtc"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, ContractError::RefundAddressLocked {}));
+
+        // the bidder themselves can still move their refunds
+        contract
+            .bid_on_behalf(
+                &mut app,
+                &user,
+                &user,
+                Some(user_wallet.to_string()),
+                &coins(1, "ubtc"),
+            )
+            .unwrap();
+
+        // without a recorded address, the bidder's funds can't be redirected either
+        contract
+            .bid(&mut app, &bidder, &coins(150_000, "ubtc"))
+            .unwrap();
+        let err = contract
+            .bid_on_behalf(
+                &mut app,
+                &attacker,
+                &bidder,
+                Some(attacker.to_string()),
+                &coins(1, "ubtc"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, ContractError::RefundAddressLocked {}));
+
+        contract
+            .bid(&mut app, &bidder, &coins(50_000, "ubtc"))
+            .unwrap();
+        contract.close_bidding(&mut app, &owner).unwrap();
+        contract.retract_funds(&mut app, &user, None).unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&user_wallet).unwrap(),
+            coins(100_001, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&attacker).unwrap(),
+            coins(1, "ubtc")
+        );
+    }
+
+    #[test]
+    fn bids_in_other_denoms_are_normalized_with_fixed_rates() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = App::new(|router, _api, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &bidder,
+                    vec![
+                        coin(100_000, "ubtc"),
+                        coin(100_000, "uusdc"),
+                        coin(100_000, "uatom"),
+                    ],
+                )
+                .unwrap();
+
+            router
+                .bank
+                .init_balance(storage, &bidder_two, coins(400_000, "uusdc"))
+                .unwrap();
+        });
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                accepted_denoms: Some(vec!["uusdc".to_string()]),
+                price_source: Some(PriceSource::Fixed {
+                    rates: vec![DenomRate {
+                        denom: "uusdc".to_string(),
+                        rate: Decimal::percent(50),
+                    }],
+                }),
+                ..default_instantiate_msg()
+            },
+        );
+
+        let err = contract
+            .bid(&mut app, &bidder, &coins(100_000, "uatom"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedDenom { .. }));
+
+        contract
+            .bid(
+                &mut app,
+                &bidder,
+                &[coin(100_000, "ubtc"), coin(100_000, "uusdc")],
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract
+                .query_user_bid(&app, bidder.to_string())
+                .unwrap()
+                .bid,
+            coin(150_000, "ubtc")
+        );
+
+        let err = contract
+            .bid(&mut app, &bidder_two, &coins(300_000, "uusdc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow { .. }));
+
+        contract
+            .bid(&mut app, &bidder_two, &coins(400_000, "uusdc"))
+            .unwrap();
+
+        assert_eq!(
+            contract.query_auction_status(&app).unwrap().highest_bid,
+            Some(BidResponse {
+                bidder: bidder_two.to_string(),
+                bid: coin(200_000, "ubtc")
+            })
+        );
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        assert_eq!(
+            contract
+                .query_user_deposits(&app, bidder.to_string())
+                .unwrap(),
+            DepositsResponse {
+                bidder: bidder.to_string(),
+                deposits: vec![coin(100_000, "ubtc"), coin(100_000, "uusdc")]
+            }
+        );
+
+        contract.retract_funds(&mut app, &bidder, None).unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            vec![
+                coin(100_000, "uatom"),
+                coin(100_000, "ubtc"),
+                coin(100_000, "uusdc")
+            ]
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            coins(400_000, "uusdc")
+        );
+    }
+
+    #[test]
+    fn bids_in_other_denoms_are_priced_by_the_oracle() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[(&bidder, coins(200_000, "ueth"))]);
+
+        let contract_id = app.store_code(auctioning_contract());
+        let oracle_id = app.store_code(oracle_contract());
+
+        let oracle = app
+            .instantiate_contract(
+                oracle_id,
+                owner.clone(),
+                &Decimal::percent(200),
+                &[],
+                "Oracle",
+                None,
+            )
+            .unwrap();
+
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &InstantiateMsg {
+                accepted_denoms: Some(vec!["ueth".to_string()]),
+                price_source: Some(PriceSource::Oracle {
+                    contract: oracle.to_string(),
+                }),
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        contract
+            .bid(&mut app, &bidder, &coins(60_000, "ueth"))
+            .unwrap();
+
+        assert_eq!(
+            contract
+                .query_user_bid(&app, bidder.to_string())
+                .unwrap()
+                .bid,
+            coin(120_000, "ubtc")
+        );
+
+        let fixed_rates = PriceSource::Fixed {
+            rates: vec![DenomRate {
+                denom: "ueth".to_string(),
+                rate: Decimal::percent(100),
+            }],
+        };
+
+        let err = contract
+            .update_price_source(&mut app, &bidder, fixed_rates.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        contract
+            .update_price_source(&mut app, &owner, fixed_rates)
+            .unwrap();
+
+        contract
+            .bid(&mut app, &bidder, &coins(60_000, "ueth"))
+            .unwrap();
+
+        assert_eq!(
+            contract
+                .query_user_bid(&app, bidder.to_string())
+                .unwrap()
+                .bid,
+            coin(180_000, "ubtc")
+        );
+        assert_eq!(
+            contract
+                .query_user_deposits(&app, bidder.to_string())
+                .unwrap()
+                .deposits,
+            coins(120_000, "ueth")
+        );
+    }
+
+    #[test]
+    fn hooks_run_with_a_gas_limit() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        contract::instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ExecuteMsg::AddHook {
+                addr: "receiver".to_string(),
+            },
+        )
+        .unwrap();
+
+        let res = contract::execute(
+            deps.as_mut(),
+            env,
+            mock_info("bidder", &coins(100_000, "ubtc")),
+            ExecuteMsg::Bid {
+                bidder: None,
+                refund_address: None,
+                ibc_refund: None,
+                proof: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].gas_limit, Some(HOOK_GAS_LIMIT));
+    }
+
+    #[test]
+    fn ibc_bids_are_refunded_over_ibc() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = "owner";
+        // address the ibc hooks middleware derives for the remote sender, it holds no keys
+        let hook_sender = "ibc_hook_sender";
+        let bidder = "bidder";
+        let ibc_denom = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+        contract::instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(owner, &[]),
+            InstantiateMsg {
+                auction_item_title: "Test auction".to_string(),
+                accepted_denoms: Some(vec![ibc_denom.to_string()]),
+                price_source: Some(PriceSource::Fixed {
+                    rates: vec![DenomRate {
+                        denom: ibc_denom.to_string(),
+                        rate: Decimal::one(),
+                    }],
+                }),
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        let ibc_bid = |ibc_refund| ExecuteMsg::Bid {
+            bidder: None,
+            refund_address: None,
+            ibc_refund,
+            proof: None,
+        };
+
+        // memo of an ICS-20 transfer, executed by the hooks middleware with the transferred funds
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(hook_sender, &coins(100_000, ibc_denom)),
+            ibc_bid(Some(IbcRefundRoute {
+                channel_id: "channel-0".to_string(),
+                receiver: String::new(),
+            })),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcRefundRoute {}));
+
+        // nobody else can route the refunds of the hook sender
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(bidder, &coins(1, "ubtc")),
+            ExecuteMsg::Bid {
+                bidder: Some(hook_sender.to_string()),
+                refund_address: None,
+                ibc_refund: Some(IbcRefundRoute {
+                    channel_id: "channel-1".to_string(),
+                    receiver: "osmo1attacker".to_string(),
+                }),
+                proof: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ForeignIbcRefundRoute {}));
+
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(hook_sender, &coins(100_000, ibc_denom)),
+            ibc_bid(Some(IbcRefundRoute {
+                channel_id: "channel-0".to_string(),
+                receiver: "osmo1remotebidder".to_string(),
+            })),
+        )
+        .unwrap();
+
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(bidder, &coins(150_000, "ubtc")),
+            ibc_bid(None),
+        )
+        .unwrap();
+
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(owner, &[]),
+            ExecuteMsg::CloseBidding {},
+        )
+        .unwrap();
+
+        let res = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::ProcessRefunds { limit: None },
+        )
+        .unwrap();
+
+        let transfer = ics20::transfer_msg(
+            "channel-0",
+            env.contract.address.as_str(),
+            "osmo1remotebidder",
+            &coin(100_000, ibc_denom),
+            env.block.time.plus_seconds(600),
+        );
+        let refunds: Vec<CosmosMsg> = res.messages.into_iter().map(|msg| msg.msg).collect();
+        assert_eq!(refunds, vec![transfer.clone()]);
+
+        // the channel was closed, nothing left the contract
+        let transfer_reply = |result| Reply { id: 3, result };
+        contract::reply(
+            deps.as_mut(),
+            env.clone(),
+            transfer_reply(SubMsgResult::Err("channel closed".to_string())),
+        )
+        .unwrap();
+
+        let status =
+            contract::query::bidder_status(deps.as_ref(), env.clone(), hook_sender.to_string())
+                .unwrap();
+        assert_eq!(status.withdrawable, coins(100_000, ibc_denom));
+
+        // retried through another memo, this time the transfer goes out as packet 7 and times out
+        let res = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(hook_sender, &[]),
+            ExecuteMsg::RetractFunds {
+                withdraw_address: None,
+                amount: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, transfer);
+
+        contract::reply(
+            deps.as_mut(),
+            env.clone(),
+            transfer_reply(SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(Binary::from([0x08, 0x07])),
+            })),
+        )
+        .unwrap();
+
+        let status =
+            contract::query::bidder_status(deps.as_ref(), env.clone(), hook_sender.to_string())
+                .unwrap();
+        assert_eq!(status.withdrawable, vec![]);
+
+        contract::sudo(
+            deps.as_mut(),
+            env.clone(),
+            AuctionSudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+                channel: "channel-0".to_string(),
+                sequence: 7,
+            }),
+        )
+        .unwrap();
+
+        let status =
+            contract::query::bidder_status(deps.as_ref(), env.clone(), hook_sender.to_string())
+                .unwrap();
+        assert_eq!(status.withdrawable, coins(100_000, ibc_denom));
+
+        // a transfer without a known sequence still goes out, it just can't be tracked
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(hook_sender, &[]),
+            ExecuteMsg::RetractFunds {
+                withdraw_address: None,
+                amount: None,
+            },
+        )
+        .unwrap();
+
+        let res = contract::reply(
+            deps.as_mut(),
+            env.clone(),
+            transfer_reply(SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            })),
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "action" && attr.value == "ibc_refund_untracked"));
+
+        let status =
+            contract::query::bidder_status(deps.as_ref(), env, hook_sender.to_string()).unwrap();
+        assert_eq!(status.withdrawable, vec![]);
+    }
+
+    #[test]
+    fn companion_contracts_bid_over_ibc_channel() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let channel_id = "channel-7";
+
+        contract::instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            InstantiateMsg {
+                auction_item_title: "Test auction".to_string(),
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        // only companions the owner allowed can open channels
+        let err = ibc::ibc_channel_open(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_channel_open_init(channel_id, IbcOrder::Unordered, ibc::IBC_VERSION),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::UnknownIbcCounterparty { connection_id, port_id }
+                if connection_id == "connection-2" && port_id == "their_port"
+        ));
+
+        let allow_counterparty = ExecuteMsg::AllowIbcCounterparty {
+            connection_id: "connection-2".to_string(),
+            port_id: "their_port".to_string(),
+        };
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bidder", &[]),
+            allow_counterparty.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            allow_counterparty,
+        )
+        .unwrap();
+
+        let err = ibc::ibc_channel_open(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_channel_open_init(channel_id, IbcOrder::Ordered, ibc::IBC_VERSION),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OrderedChannel {}));
+
+        let err = ibc::ibc_channel_open(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_channel_open_init(channel_id, IbcOrder::Unordered, "ics20-1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcVersion { .. }));
+
+        ibc::ibc_channel_open(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_channel_open_init(channel_id, IbcOrder::Unordered, ibc::IBC_VERSION),
+        )
+        .unwrap();
+        ibc::ibc_channel_connect(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_channel_connect_ack(channel_id, IbcOrder::Unordered, ibc::IBC_VERSION),
+        )
+        .unwrap();
+
+        let remote_bid = |bidder: &str, amount| AuctionPacketMsg::Bid {
+            bidder: bidder.to_string(),
+            amount: coin(amount, "ubtc"),
+            proof: None,
+        };
+
+        let res = ibc::ibc_packet_receive(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_packet_recv(channel_id, &remote_bid("osmo1remote", 100_000)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            from_binary::<AuctionPacketAck>(&res.acknowledgement).unwrap(),
+            AuctionPacketAck::Result(to_binary(&coin(100_000, "ubtc")).unwrap())
+        );
+        assert_eq!(
+            contract::query::get_user_bid(deps.as_ref(), "channel-7/osmo1remote".to_string())
+                .unwrap()
+                .bid,
+            coin(100_000, "ubtc")
+        );
+        let status = contract::query::bidder_status(
+            deps.as_ref(),
+            env.clone(),
+            "channel-7/osmo1remote".to_string(),
+        )
+        .unwrap();
+        assert!(status.leading);
+
+        // a rejected bid is acknowledged with an error so the companion can release the escrow
+        let res = ibc::ibc_packet_receive(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_packet_recv(channel_id, &remote_bid("osmo1other", 50_000)).unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            from_binary::<AuctionPacketAck>(&res.acknowledgement).unwrap(),
+            AuctionPacketAck::Error(_)
+        ));
+        assert_eq!(
+            contract::query::get_user_bid(deps.as_ref(), "channel-7/osmo1other".to_string())
+                .unwrap()
+                .bid,
+            coin(0, "ubtc")
+        );
+
+        let res = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bidder", &coins(150_000, "ubtc")),
+            ExecuteMsg::Bid {
+                bidder: None,
+                refund_address: None,
+                ibc_refund: None,
+                proof: None,
+            },
+        )
+        .unwrap();
+
+        let outbid = AuctionPacketMsg::Outbid {
+            bidder: "osmo1remote".to_string(),
+            bid: coin(100_000, "ubtc"),
+            outbid_by: "bidder".to_string(),
+        };
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id: channel_id.to_string(),
+                data: to_binary(&outbid).unwrap(),
+                timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(600)),
+            })
+        );
+        assert_eq!(
+            contract::query::get_user_bid(deps.as_ref(), "channel-7/osmo1remote".to_string())
+                .unwrap()
+                .bid,
+            coin(0, "ubtc")
+        );
+
+        // the companion never learned about the outbid, so its escrow still backs the bid
+        ibc::ibc_packet_timeout(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_packet_timeout(channel_id, &outbid).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            contract::query::get_user_bid(deps.as_ref(), "channel-7/osmo1remote".to_string())
+                .unwrap()
+                .bid,
+            coin(100_000, "ubtc")
+        );
+
+        let res = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ExecuteMsg::CloseBidding {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id: channel_id.to_string(),
+                data: to_binary(&AuctionPacketMsg::Settled {
+                    winner: None,
+                    bid: Some(coin(150_000, "ubtc")),
+                })
+                .unwrap(),
+                timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(600)),
+            })
+        );
+    }
+
+    #[test]
+    fn commission_is_paid_on_settlement() {
+        let house = Addr::unchecked("house");
+        let seller = Addr::unchecked("seller");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[(&bidder, coins(100_000, "ubtc"))]);
+        let contract = instantiate_auction(
+            &mut app,
+            &house,
+            &InstantiateMsg {
+                owner: Some(seller.to_string()),
+                commission_percentage: Some(Decimal::percent(10)),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+        contract.close_bidding(&mut app, &seller).unwrap();
+
+        // the escrow can't be settled while settlement is paused
+        contract
+            .pause(&mut app, &seller, Some(PauseScope::Settlement))
+            .unwrap();
+        let err = contract.confirm_delivery(&mut app, &bidder).unwrap_err();
+        assert!(matches!(err, ContractError::SettlementPaused {}));
+        contract
+            .unpause(&mut app, &seller, Some(PauseScope::Settlement))
+            .unwrap();
+
+        contract.confirm_delivery(&mut app, &bidder).unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&seller).unwrap(),
+            coins(90_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&house).unwrap(),
+            coins(10_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn remote_winner_is_paid_out_by_its_companion() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let channel_id = "channel-7";
+
+        contract::instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            InstantiateMsg {
+                delivery_timeout: Some(100),
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ExecuteMsg::AllowIbcCounterparty {
+                connection_id: "connection-2".to_string(),
+                port_id: "their_port".to_string(),
+            },
+        )
+        .unwrap();
+        ibc::ibc_channel_connect(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_channel_connect_ack(channel_id, IbcOrder::Unordered, ibc::IBC_VERSION),
+        )
+        .unwrap();
+
+        ibc::ibc_packet_receive(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_packet_recv(
+                channel_id,
+                &AuctionPacketMsg::Bid {
+                    bidder: "osmo1remote".to_string(),
+                    amount: coin(100_000, "ubtc"),
+                    proof: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ExecuteMsg::CloseBidding {},
+        )
+        .unwrap();
+
+        // the companion keeps the winning funds, the escrow here only tracks the delivery
+        let escrow = ESCROW.load(&deps.storage).unwrap();
+        assert_eq!(escrow.funds, vec![]);
+        assert_eq!(escrow.remote_channel, Some(channel_id.to_string()));
+        assert_eq!(escrow.status, EscrowStatus::AwaitingDelivery);
+
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ReleaseEscrow {},
+        )
+        .unwrap();
+
+        let payout = AuctionPacketMsg::Payout {
+            winner: "osmo1remote".to_string(),
+            seller: "owner".to_string(),
+            buyer_share: Decimal::zero(),
+            commission: Decimal::percent(5),
+            commission_recipient: "owner".to_string(),
+        };
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id: channel_id.to_string(),
+                data: to_binary(&payout).unwrap(),
+                timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(600)),
+            })
+        );
+
+        // indexers see the settlement like for a local winner
+        let settle = res
+            .events
+            .iter()
+            .find(|event| event.ty == "auction_settle")
+            .unwrap();
+        assert_eq!(
+            settle
+                .attributes
+                .iter()
+                .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("auction_id", env.contract.address.as_str()),
+                ("bidder", "channel-7/osmo1remote"),
+                ("new_total", "100000ubtc"),
+                ("amount", "95000ubtc"),
+                ("commission", "5000ubtc"),
+            ]
+        );
+
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::RetryPayout {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoFailedPayout {}));
+
+        // a lost payout keeps the escrow open until it is sent again
+        ibc::ibc_packet_timeout(
+            deps.as_mut(),
+            env.clone(),
+            mock_ibc_packet_timeout(channel_id, &payout).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            ESCROW.load(&deps.storage).unwrap().status,
+            EscrowStatus::PayoutFailed
+        );
+
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ReleaseEscrow {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingEscrow {}));
+
+        let res = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::RetryPayout {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id: channel_id.to_string(),
+                data: to_binary(&payout).unwrap(),
+                timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(600)),
+            })
+        );
+        assert_eq!(
+            ESCROW.load(&deps.storage).unwrap().status,
+            EscrowStatus::Settled
+        );
+    }
+
+    #[test]
+    fn winning_bid_is_escrowed_until_delivery() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(100_000, "ubtc")),
+            (&bidder_two, coins(150_000, "ubtc")),
+        ]);
+
+        let contract_id = app.store_code(auctioning_contract());
+
+        let instantiate_msg = InstantiateMsg {
+            auction_item_title: "Vintage watch".to_string(),
+            delivery_timeout: Some(100),
+            ..default_instantiate_msg()
+        };
+
+        // the winner confirms delivery
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &instantiate_msg,
+        )
+        .unwrap();
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        let err = contract.confirm_delivery(&mut app, &bidder).unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingEscrow {}));
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        assert_eq!(
+            contract.query_escrow(&app).unwrap(),
+            Some(DeliveryEscrow {
+                winner: bidder.clone(),
+                bid: coin(100_000, "ubtc"),
+                funds: coins(100_000, "ubtc"),
+                release_at: app.block_info().time.plus_seconds(100),
+                status: EscrowStatus::AwaitingDelivery,
+                remote_channel: None,
+                buyer_share: None,
+            })
+        );
+
+        let err = contract.confirm_delivery(&mut app, &owner).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        let err = contract.open_dispute(&mut app, &bidder).unwrap_err();
+        assert!(matches!(err, ContractError::NoArbiter {}));
+
+        contract.confirm_delivery(&mut app, &bidder).unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&owner).unwrap(),
+            coins(100_000, "ubtc")
+        );
+        assert_eq!(
+            contract.query_escrow(&app).unwrap().unwrap().status,
+            EscrowStatus::Settled
+        );
+
+        let err = contract.release_escrow(&mut app, &owner).unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingEscrow {}));
+
+        // nobody confirms, the funds are released once the timeout passes
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &instantiate_msg,
+        )
+        .unwrap();
+
+        contract
+            .bid(&mut app, &bidder_two, &coins(150_000, "ubtc"))
+            .unwrap();
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        let err = contract.release_escrow(&mut app, &owner).unwrap_err();
+        assert!(matches!(err, ContractError::DeliveryPending { .. }));
+
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+        contract.release_escrow(&mut app, &bidder).unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&owner).unwrap(),
+            coins(250_000, "ubtc")
+        );
+    }
+
+    #[test]
+    fn arbiter_splits_disputed_escrow() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let arbiter = Addr::unchecked("arbiter");
+
+        let mut app = mock_app(&[(&bidder, coins(100_000, "ubtc"))]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                arbiter: Some(arbiter.to_string()),
+                delivery_timeout: Some(100),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+        contract.close_bidding(&mut app, &owner).unwrap();
+
+        let err = contract
+            .resolve_dispute(&mut app, &arbiter, Decimal::percent(50))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::EscrowNotDisputed {}));
+
+        let err = contract.open_dispute(&mut app, &arbiter).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        contract.open_dispute(&mut app, &bidder).unwrap();
+
+        // a dispute holds the funds past the delivery timeout
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let err = contract.release_escrow(&mut app, &owner).unwrap_err();
+        assert!(matches!(err, ContractError::EscrowDisputed {}));
+
+        let err = contract
+            .resolve_dispute(&mut app, &owner, Decimal::percent(50))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        let err = contract
+            .resolve_dispute(&mut app, &arbiter, Decimal::percent(150))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBuyerShare {}));
+
+        contract
+            .resolve_dispute(&mut app, &arbiter, Decimal::percent(30))
+            .unwrap();
+
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(30_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(&owner).unwrap(),
+            coins(70_000, "ubtc")
+        );
+        assert_eq!(
+            app.wrap().query_all_balances(contract.addr()).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn owner_can_update_item_until_bidding_starts() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[(&bidder, coins(100_000, "ubtc"))]);
+
+        let contract_id = app.store_code(auctioning_contract());
+
+        let item = ItemMetadata {
+            description: Some("Automatic, steel case".to_string()),
+            uri: Some(
+                "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string(),
+            ),
+            category: Some("watches".to_string()),
+            attributes: vec![ItemAttribute {
+                key: "year".to_string(),
+                value: "1968".to_string(),
+            }],
+            content_hash: Some(hex::encode(Sha256::digest(b"Automatic, steel case"))),
+        };
+
+        let mut instantiate_msg = InstantiateMsg {
+            owner: None,
+            auction_item_title: "Vintage watch".to_string(),
+            item: Some(ItemMetadata {
+                category: Some("c".repeat(65)),
+                ..item.clone()
+            }),
+            commission_percentage: None,
+            commission_recipient: None,
+            merkle_root: None,
+            max_bid_per_bidder: None,
+            guardian: None,
+            staking: None,
+            auto_refund_outbid: None,
+            retract_cooldown: None,
+            refund_keeper_tip: None,
+            accepted_denoms: None,
+            price_source: None,
+            arbiter: None,
+            delivery_timeout: None,
+        };
+
+        let err = app
+            .instantiate_contract(
+                contract_id,
+                owner.clone(),
+                &instantiate_msg,
+                &[],
+                "Test auction contract",
+                None,
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::ItemMetadataTooLarge { .. }));
+
+        instantiate_msg.item = Some(item.clone());
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &instantiate_msg,
+        )
+        .unwrap();
+
+        assert_eq!(
+            contract.query_item(&app).unwrap(),
+            ItemResponse {
+                title: "Vintage watch".to_string(),
+                metadata: item.clone(),
+            }
+        );
+
+        let updated_item = ItemMetadata {
+            description: Some("Automatic, steel case, original box".to_string()),
+            ..item
+        };
+
+        let err = contract
+            .update_item(&mut app, &bidder, updated_item.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        let err = contract
+            .update_item(
+                &mut app,
+                &owner,
+                ItemMetadata {
+                    content_hash: Some("not a hash".to_string()),
+                    ..updated_item.clone()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Hex(_)));
+
+        contract
+            .update_item(&mut app, &owner, updated_item.clone())
+            .unwrap();
+        assert_eq!(contract.query_item(&app).unwrap().metadata, updated_item);
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        let err = contract
+            .update_item(&mut app, &owner, ItemMetadata::default())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::BiddingStarted {}));
+    }
+
+    #[test]
+    fn owner_can_update_config_before_third_party_bids() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[
+            (&owner, coins(10_000, "ubtc")),
+            (&bidder, coins(100_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintgae watch".to_string(),
+                max_bid_per_bidder: Some(Uint128::new(50_000)),
+                retract_cooldown: Some(60),
+                arbiter: Some("arbiter".to_string()),
+                ..default_instantiate_msg()
+            },
+        );
+
+        let update_config = |auction_item_title: &str| ExecuteMsg::UpdateConfig {
+            auction_item_title: Some(auction_item_title.to_string()),
+            commission_percentage: Some(Decimal::percent(2)),
+            commission_recipient: Some("treasury".to_string()),
+            max_bid_per_bidder: None,
+            guardian: None,
+            retract_cooldown: None,
+            refund_keeper_tip: None,
+            arbiter: None,
+            delivery_timeout: None,
+            clear: Some(vec![
+                OptionalSetting::MaxBidPerBidder,
+                OptionalSetting::RetractCooldown,
+                OptionalSetting::Arbiter,
+            ]),
+        };
+
+        let err = app
+            .execute_contract(
+                bidder.clone(),
+                contract.addr().clone(),
+                &update_config("Vintage watch"),
+                &[],
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+        // the seller's own bids don't lock the configuration
+        contract
+            .bid(&mut app, &owner, &coins(10_000, "ubtc"))
+            .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contract.addr().clone(),
+                &update_config("Vintage watch"),
+                &[],
+            )
+            .unwrap();
+
+        let event = res
+            .events
+            .iter()
+            .find(|event| event.ty == "wasm-update_config")
+            .unwrap();
+        assert_eq!(
+            event
+                .attributes
+                .iter()
+                .filter(|attr| attr.key != "_contract_addr")
+                .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("auction_item_title", "Vintage watch"),
+                ("commission_percentage", "0.02"),
+                ("commission_recipient", "treasury"),
+                ("max_bid_per_bidder", "none"),
+                ("retract_cooldown", "none"),
+                ("arbiter", "none"),
+            ]
+        );
+
+        let config = contract.query_config(&app).unwrap();
+        assert_eq!(config.auction_item_title, "Vintage watch");
+        assert_eq!(config.commission_percentage, Decimal::percent(2));
+        assert_eq!(config.commission_recipient, "treasury");
+        assert_eq!(config.max_bid_per_bidder, None);
+        assert_eq!(config.retract_cooldown, None);
+        assert_eq!(config.arbiter, None);
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contract.addr().clone(),
+                &update_config("Vintage watch, 1968"),
+                &[],
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::BiddingStarted {}));
+
+        // bids carried over from before a migration have no bid time recorded
+        let mut deps = mock_dependencies();
+        contract::instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+        BIDS.save(
+            &mut deps.storage,
+            &Addr::unchecked("legacy_bidder"),
+            &coin(100_000, "ubtc"),
+        )
+        .unwrap();
+
+        let err = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update_config("Vintage watch, 1968"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BiddingStarted {}));
+    }
+
+    #[test]
+    fn empty_auctions_close_without_a_winner() {
+        let owner = Addr::unchecked("sender");
+
+        let mut app = mock_app(&[(&owner, coins(100_000, "ubtc"))]);
+
+        let contract_id = app.store_code(auctioning_contract());
+
+        let instantiate_msg = default_instantiate_msg();
+
+        // the seller can't open with a bid of their own
+        let err = app
+            .instantiate_contract(
+                contract_id,
+                owner.clone(),
+                &instantiate_msg,
+                &coins(100_000, "ubtc"),
+                "Test auction contract",
+                None,
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::UnexpectedFunds {}));
+
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &instantiate_msg,
+        )
+        .unwrap();
+
+        let err = contract.retract_funds(&mut app, &owner, None).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+        contract.process_refunds(&mut app, &owner, None).unwrap();
+
+        let status = contract.query_auction_status(&app).unwrap();
+        assert!(!status.active);
+        assert_eq!(status.highest_bid, None);
+        assert_eq!(status.bidders_count, 0);
+        assert_eq!(contract.query_escrow(&app).unwrap(), None);
+    }
+
+    #[test]
+    fn first_release_deployments_migrate() {
+        let seller = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        // the first release accepted any spelling of an address
+        let bidder_upper = Addr::unchecked("BIDDER");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(100, "ubtc")),
+            (&bidder_upper, coins(50, "ubtc")),
+            (&bidder_two, coins(200, "ubtc")),
+        ]);
+
+        let legacy_id = app.store_code(legacy_auctioning_contract());
+        let addr = app
+            .instantiate_contract(
+                legacy_id,
+                seller.clone(),
+                &LegacyInstantiateMsg {
+                    owner: Some("SENDER".to_string()),
+                    auction_item_title: "Vintage watch".to_string(),
+                    commission_percentage: None,
+                },
+                &[],
+                "Legacy auction contract",
+                Some(seller.to_string()),
+            )
+            .unwrap();
+        for (sender, amount) in [(&bidder, 100), (&bidder_upper, 50), (&bidder_two, 200)] {
+            app.execute_contract(
+                sender.clone(),
+                addr.clone(),
+                &LegacyExecuteMsg::Bid {},
+                &coins(amount, "ubtc"),
+            )
+            .unwrap();
+        }
+
+        let contract = AuctionContract::from(addr);
+        let code_id = AuctionContract::store_code(&mut app);
+        contract.migrate(&mut app, &seller, code_id).unwrap();
+
+        // the empty bid of the seller is gone and both spellings of the bidder are merged
+        let status = contract.query_auction_status(&app).unwrap();
+        assert_eq!(status.owner, seller.to_string());
+        assert_eq!(status.bidders_count, 2);
+        assert_eq!(
+            contract
+                .query_user_bid(&app, bidder.to_string())
+                .unwrap()
+                .bid,
+            coin(150, "ubtc")
+        );
+        assert_eq!(
+            contract
+                .query_user_deposits(&app, bidder.to_string())
+                .unwrap()
+                .deposits,
+            coins(150, "ubtc")
+        );
+        contract
+            .query_user_bid(&app, bidder_upper.to_string())
+            .unwrap_err();
+
+        let config = contract.query_config(&app).unwrap();
+        assert_eq!(config.guardian, seller.to_string());
+        assert_eq!(config.commission_recipient, seller.to_string());
+        assert_eq!(config.refund_keeper_tip, Decimal::zero());
+        assert_eq!(config.delivery_timeout, 60 * 60 * 24 * 30);
+        assert!(!config.auto_refund_outbid);
+        assert_eq!(contract.query_item(&app).unwrap().title, "Vintage watch");
+
+        // bids placed before the migration lock the configuration like any other
+        let err = app
+            .execute_contract(
+                seller.clone(),
+                contract.addr().clone(),
+                &ExecuteMsg::UpdateConfig {
+                    auction_item_title: None,
+                    commission_percentage: Some(Decimal::zero()),
+                    commission_recipient: None,
+                    max_bid_per_bidder: None,
+                    guardian: None,
+                    retract_cooldown: None,
+                    refund_keeper_tip: None,
+                    arbiter: None,
+                    delivery_timeout: None,
+                    clear: None,
+                },
+                &[],
+            )
+            .unwrap_err()
+            .downcast::<ContractError>()
+            .unwrap();
+        assert!(matches!(err, ContractError::BiddingStarted {}));
+
+        contract.retract_funds(&mut app, &bidder, None).unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(&bidder).unwrap(),
+            coins(150, "ubtc")
+        );
+
+        contract.close_bidding(&mut app, &seller).unwrap();
+        contract.confirm_delivery(&mut app, &bidder_two).unwrap();
+        assert_eq!(
+            app.wrap().query_all_balances(&seller).unwrap(),
+            coins(200, "ubtc")
+        );
+
+        // hooks registered under another spelling are keyed by their canonical address
+        let mut deps = mock_dependencies();
+        contract::instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+        HOOKS
+            .save(
+                &mut deps.storage,
+                &Addr::unchecked("HOOK_RECEIVER"),
+                &Empty {},
+            )
+            .unwrap();
+        contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            contract::query::hooks(deps.as_ref()).unwrap(),
+            HooksResponse {
+                hooks: vec!["hook_receiver".to_string()]
+            }
+        );
+
+        // refusing to migrate storage of a different contract
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:other", "1.0.0").unwrap();
+        let err = contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMigration { .. }));
+    }
+
+    /// Attributes of the `ty` event, without the contract address multitest adds to it
+    fn event_attributes(res: &AppResponse, ty: &str) -> Vec<(String, String)> {
+        res.events
+            .iter()
+            .find(|event| event.ty == ty)
+            .unwrap_or_else(|| panic!("missing {} event", ty))
+            .attributes
+            .iter()
+            .filter(|attr| attr.key != "_contract_addr")
+            .map(|attr| (attr.key.clone(), attr.value.clone()))
+            .collect()
+    }
+
+    fn attrs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn auction_emits_structured_events() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(100_000, "ubtc")),
+            (&bidder_two, coins(150_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                auto_refund_outbid: Some(true),
+                ..default_instantiate_msg()
+            },
+        );
+        let auction_id = contract.addr().as_str();
+
+        let bid = ExecuteMsg::Bid {
+            bidder: None,
+            refund_address: None,
+            ibc_refund: None,
+            proof: None,
+        };
+
+        let res = app
+            .execute_contract(
+                bidder.clone(),
+                contract.addr().clone(),
+                &bid,
+                &coins(100_000, "ubtc"),
+            )
+            .unwrap();
+        assert_eq!(
+            event_attributes(&res, "wasm-auction_bid"),
+            attrs(&[
+                ("auction_id", auction_id),
+                ("bidder", "bidder"),
+                ("increment", "100000ubtc"),
+                ("new_total", "100000ubtc"),
+            ])
+        );
+
+        let res = app
+            .execute_contract(
+                bidder_two.clone(),
+                contract.addr().clone(),
+                &bid,
+                &coins(150_000, "ubtc"),
+            )
+            .unwrap();
+        assert_eq!(
+            event_attributes(&res, "wasm-auction_bid"),
+            attrs(&[
+                ("auction_id", auction_id),
+                ("bidder", "bidder_two"),
+                ("increment", "150000ubtc"),
+                ("new_total", "150000ubtc"),
+                ("previous_leader", "bidder"),
+            ])
+        );
+        assert_eq!(
+            event_attributes(&res, "wasm-auction_refund"),
+            attrs(&[
+                ("auction_id", auction_id),
+                ("bidder", "bidder"),
+                ("amount", "100000ubtc"),
+            ])
+        );
+
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contract.addr().clone(),
+                &ExecuteMsg::CloseBidding {},
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attributes(&res, "wasm-auction_close"),
+            attrs(&[
+                ("auction_id", auction_id),
+                ("bidder", "bidder_two"),
+                ("new_total", "150000ubtc"),
+            ])
+        );
+
+        let res = app
+            .execute_contract(
+                bidder_two.clone(),
+                contract.addr().clone(),
+                &ExecuteMsg::ConfirmDelivery {},
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attributes(&res, "wasm-auction_settle"),
+            attrs(&[
+                ("auction_id", auction_id),
+                ("bidder", "bidder_two"),
+                ("new_total", "150000ubtc"),
+                ("amount", "142500ubtc"),
+                ("commission", "7500ubtc"),
+            ])
+        );
+        assert!(!res
+            .events
+            .iter()
+            .any(|event| event.ty == "wasm-auction_refund"));
+    }
+
+    #[test]
+    fn interface_builds_messages_and_queries() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+
+        let mut app = mock_app(&[(&bidder, coins(100_000, "ubtc"))]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                ..default_instantiate_msg()
+            },
+        );
+        let auction = AuctionInterface::new(contract.addr());
+
+        app.execute(bidder.clone(), auction.bid(coins(100_000, "ubtc")).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            auction.user_bid(&app.wrap(), &bidder).unwrap(),
+            BidResponse {
+                bidder: bidder.to_string(),
+                bid: coin(100_000, "ubtc"),
+            }
+        );
+
+        app.execute(owner.clone(), auction.close_bidding().unwrap())
+            .unwrap();
+
+        let status = auction.auction_status(&app.wrap()).unwrap();
+        assert!(!status.active);
+        assert_eq!(
+            auction.escrow(&app.wrap()).unwrap().unwrap().winner,
+            bidder.to_string()
+        );
+    }
+
+    #[test]
+    fn contracts_bid_through_helper() {
+        let owner = Addr::unchecked("sender");
+        let dao = Addr::unchecked("dao");
+
+        let mut app = App::default();
+
+        let contract_id = app.store_code(auctioning_contract());
+        let treasury_id = app.store_code(treasury_contract());
+
+        let contract = AuctionContract::instantiate(
+            &mut app,
+            contract_id,
+            &owner,
+            None,
+            "Test auction contract",
+            &[],
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        let treasury = app
+            .instantiate_contract(treasury_id, dao.clone(), &Empty {}, &[], "Treasury", None)
+            .unwrap();
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: treasury.to_string(),
+            amount: coins(100_000, "ubtc"),
+        }))
+        .unwrap();
+
+        let treasury_bid = TreasuryBidMsg {
+            auction: contract.addr().to_string(),
+            amount: coin(100_000, "ubtc"),
+        };
+        app.execute_contract(dao.clone(), treasury.clone(), &treasury_bid, &[])
+            .unwrap();
+
+        let auction = CwAuctionContract(contract.addr().clone());
+        assert_eq!(
+            auction.user_bid(&app.wrap(), &treasury).unwrap().bid,
+            coin(100_000, "ubtc")
+        );
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+        app.execute_contract(dao, treasury, &treasury_bid, &[])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn simulate_bid_matches_bidding() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, vec![coin(200_000, "ubtc"), coin(10, "uatom")]),
+            (&bidder_two, coins(200_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder_two, &coins(100_000, "ubtc"))
+            .unwrap();
+
+        let simulation = contract
+            .query_simulate_bid(&app, bidder.to_string(), coins(50_000, "ubtc"), None)
+            .unwrap();
+        let err = contract
+            .bid(&mut app, &bidder, &coins(50_000, "ubtc"))
+            .unwrap_err();
+        assert_eq!(
+            simulation,
+            SimulateBidResponse {
+                success: false,
+                new_total: coin(50_000, "ubtc"),
+                would_lead: false,
+                minimum_required: coin(100_001, "ubtc"),
+                error: Some(err.to_string()),
+            }
+        );
+
+        let simulation = contract
+            .query_simulate_bid(&app, bidder.to_string(), coins(150_000, "ubtc"), None)
+            .unwrap();
+        assert_eq!(
+            simulation,
+            SimulateBidResponse {
+                success: true,
+                new_total: coin(150_000, "ubtc"),
+                would_lead: true,
+                minimum_required: coin(100_001, "ubtc"),
+                error: None,
+            }
+        );
+        contract
+            .bid(&mut app, &bidder, &coins(150_000, "ubtc"))
+            .unwrap();
+
+        // the leader only has to add something
+        let simulation = contract
+            .query_simulate_bid(&app, bidder.to_string(), coins(1, "ubtc"), None)
+            .unwrap();
+        assert!(simulation.success);
+        assert_eq!(simulation.minimum_required, coin(1, "ubtc"));
+
+        // every coin sent is checked, like for bidding
+        let funds = vec![coin(1, "ubtc"), coin(10, "uatom")];
+        let simulation = contract
+            .query_simulate_bid(&app, bidder.to_string(), funds.clone(), None)
+            .unwrap();
+        let err = contract.bid(&mut app, &bidder, &funds).unwrap_err();
+        assert!(!simulation.success);
+        assert_eq!(simulation.error, Some(err.to_string()));
+
+        // the proof is checked against the allowlist like for bidding
+        contract
+            .update_merkle_root(&mut app, &owner, Some(hex::encode(allowlist_leaf(&bidder))))
+            .unwrap();
+        let simulation = contract
+            .query_simulate_bid(&app, bidder.to_string(), coins(1, "ubtc"), Some(vec![]))
+            .unwrap();
+        assert!(simulation.success);
+
+        let simulation = contract
+            .query_simulate_bid(&app, bidder_two.to_string(), coins(100_000, "ubtc"), None)
+            .unwrap();
+        let err = contract
+            .bid(&mut app, &bidder_two, &coins(100_000, "ubtc"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+        assert!(!simulation.success);
+        assert_eq!(simulation.error, Some(err.to_string()));
+
+        contract.close_bidding(&mut app, &owner).unwrap();
+        let simulation = contract
+            .query_simulate_bid(&app, bidder.to_string(), coins(1, "ubtc"), None)
+            .unwrap();
+        assert!(!simulation.success);
+        assert_eq!(
+            simulation.error,
+            Some(ContractError::AuctionInactive {}.to_string())
+        );
+    }
+
+    #[test]
+    fn bidder_status_reports_rank_and_withdrawals() {
+        let owner = Addr::unchecked("sender");
+        let bidder = Addr::unchecked("bidder");
+        let bidder_two = Addr::unchecked("bidder_two");
+
+        let mut app = mock_app(&[
+            (&bidder, coins(100_000, "ubtc")),
+            (&bidder_two, coins(150_000, "ubtc")),
+        ]);
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                retract_cooldown: Some(100),
+                ..default_instantiate_msg()
+            },
+        );
+
+        contract
+            .bid(&mut app, &bidder, &coins(100_000, "ubtc"))
+            .unwrap();
+        contract
+            .bid(&mut app, &bidder_two, &coins(150_000, "ubtc"))
+            .unwrap();
+
+        // outbid, but still within the retract cooldown
+        assert_eq!(
+            contract
+                .query_bidder_status(&app, bidder.to_string())
+                .unwrap(),
+            BidderStatusResponse {
+                bidder: bidder.to_string(),
+                bid: coin(100_000, "ubtc"),
+                rank: Some(2),
+                leading: false,
+                amount_to_lead: coin(50_001, "ubtc"),
+                can_withdraw: false,
+                withdrawable: vec![],
+                pending_refunds: coins(100_000, "ubtc"),
+            }
+        );
+
+        assert_eq!(
+            contract
+                .query_bidder_status(&app, bidder_two.to_string())
+                .unwrap(),
+            BidderStatusResponse {
+                bidder: bidder_two.to_string(),
+                bid: coin(150_000, "ubtc"),
+                rank: Some(1),
+                leading: true,
+                amount_to_lead: coin(0, "ubtc"),
+                can_withdraw: false,
+                withdrawable: vec![],
+                pending_refunds: vec![],
+            }
+        );
+
+        let status = contract
+            .query_bidder_status(&app, "newcomer".to_string())
+            .unwrap();
+        assert_eq!(status.rank, None);
+        assert_eq!(status.amount_to_lead, coin(150_001, "ubtc"));
+
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+        let status = contract
+            .query_bidder_status(&app, bidder.to_string())
+            .unwrap();
+        assert!(status.can_withdraw);
+        assert_eq!(status.withdrawable, coins(100_000, "ubtc"));
+
+        contract.retract_funds(&mut app, &bidder, None).unwrap();
+        let status = contract
+            .query_bidder_status(&app, bidder.to_string())
+            .unwrap();
+        assert_eq!(status.rank, None);
+        assert!(!status.can_withdraw);
+        assert_eq!(status.pending_refunds, vec![]);
+    }
+
+    #[test]
+    fn config_query_returns_settings() {
+        let owner = Addr::unchecked("sender");
+        let arbiter = Addr::unchecked("arbiter");
+
+        let mut app = App::default();
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                commission_percentage: Some(Decimal::percent(5)),
+                max_bid_per_bidder: Some(Uint128::new(500_000)),
+                auto_refund_outbid: Some(true),
+                retract_cooldown: Some(60),
+                arbiter: Some(arbiter.to_string()),
+                delivery_timeout: Some(3600),
+                ..default_instantiate_msg()
+            },
+        );
+
+        assert_eq!(
+            contract.query_config(&app).unwrap(),
+            ConfigResponse {
+                owner: owner.to_string(),
+                guardian: owner.to_string(),
+                arbiter: Some(arbiter.to_string()),
+                auction_item_title: "Vintage watch".to_string(),
+                bid_denom: "ubtc".to_string(),
+                accepted_denoms: vec![],
+                price_source: None,
+                commission_percentage: Decimal::percent(5),
+                commission_recipient: owner.to_string(),
+                refund_keeper_tip: Decimal::zero(),
+                max_bid_per_bidder: Some(Uint128::new(500_000)),
+                auto_refund_outbid: true,
+                retract_cooldown: Some(60),
+                delivery_timeout: 3600,
+                merkle_root: None,
+                staking: None,
+            }
+        );
+    }
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Bid { actor: usize, amount: u128 },
+        Retract { actor: usize, amount: Option<u128> },
+        Close,
+        ConfirmDelivery { actor: usize },
+        ReleaseEscrow,
+    }
+
+    const INVARIANT_ACTORS: [&str; 5] = ["alice", "bob", "carol", "dave", "erin"];
+    const INVARIANT_BALANCE: u128 = 1_000_000;
+    const INVARIANT_DELIVERY_TIMEOUT: u64 = 3600;
+
+    fn op() -> impl Strategy<Value = Op> {
+        let actor = 0..INVARIANT_ACTORS.len();
+        prop_oneof![
+            6 => (actor.clone(), 1..200_000u128)
+                .prop_map(|(actor, amount)| Op::Bid { actor, amount }),
+            3 => (actor.clone(), proptest::option::of(1..200_000u128))
+                .prop_map(|(actor, amount)| Op::Retract { actor, amount }),
+            1 => Just(Op::Close),
+            1 => actor.prop_map(|actor| Op::ConfirmDelivery { actor }),
+            1 => Just(Op::ReleaseEscrow),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        /// Runs sequences of bids, full and partial retractions, closing and settling the escrow
+        /// from several actors, checking every outcome against a model of the bid accounting and
+        /// the invariants after each step.
+        #[test]
+        fn bid_accounting_invariants_hold(ops in proptest::collection::vec(op(), 1..40)) {
+            check_bid_accounting(&ops);
+        }
+    }
+
+    fn check_bid_accounting(ops: &[Op]) {
+        let owner = Addr::unchecked("owner");
+        let treasury = Addr::unchecked("treasury");
+        let actors = INVARIANT_ACTORS.map(Addr::unchecked);
+
+        let mut app = App::new(|router, _api, storage| {
+            for actor in &actors {
+                router
+                    .bank
+                    .init_balance(storage, actor, coins(INVARIANT_BALANCE, "ubtc"))
+                    .unwrap();
+            }
+        });
+
+        let contract = instantiate_auction(
+            &mut app,
+            &owner,
+            &InstantiateMsg {
+                auction_item_title: "Vintage watch".to_string(),
+                commission_percentage: Some(Decimal::percent(5)),
+                commission_recipient: Some(treasury.to_string()),
+                delivery_timeout: Some(INVARIANT_DELIVERY_TIMEOUT),
+                ..default_instantiate_msg()
+            },
+        );
+
+        // bid of every actor as the contract should see it
+        let mut model = [0u128; 5];
+        let mut active = true;
+        let mut highest = 0u128;
+        let mut winner = None;
+        let mut escrowed = false;
+        let mut settled = false;
+
+        for (step, op) in ops.iter().enumerate() {
+            let context = format!("step {}: {:?}", step, op);
+
+            let leader = model
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, bid)| **bid)
+                .filter(|(_, bid)| **bid > 0)
+                .map(|(actor, _)| actor);
+
+            match *op {
+                Op::Bid { actor, amount } => {
+                    let amount = amount.min(INVARIANT_BALANCE - model[actor]);
+                    if amount == 0 {
+                        continue;
+                    }
+
+                    let res = contract.bid(&mut app, &actors[actor], &coins(amount, "ubtc"));
+                    let highest_bid = model.iter().max().copied().unwrap_or_default();
+                    if active && model[actor] + amount > highest_bid {
+                        res.unwrap_or_else(|err| panic!("{}: {}", context, err));
+                        model[actor] += amount;
+                    } else {
+                        assert!(res.is_err(), "{}: bid accepted", context);
+                    }
+                }
+                Op::Retract { actor, amount } => {
+                    let available = if leader == Some(actor) {
+                        0
+                    } else {
+                        model[actor]
+                    };
+
+                    let res = match amount {
+                        Some(amount) => contract.retract_amount(&mut app, &actors[actor], amount),
+                        None => contract.retract_funds(&mut app, &actors[actor], None),
+                    };
+                    match amount {
+                        Some(amount) if amount > available => {
+                            let err = res.expect_err(&context);
+                            assert!(
+                                matches!(err, ContractError::WithdrawalTooLarge { .. }),
+                                "{}: {}",
+                                context,
+                                err
+                            );
+                        }
+                        Some(amount) => {
+                            res.unwrap_or_else(|err| panic!("{}: {}", context, err));
+                            model[actor] -= amount;
+                        }
+                        None if available > 0 => {
+                            res.unwrap_or_else(|err| panic!("{}: {}", context, err));
+                            model[actor] = 0;
+                        }
+                        None => {
+                            // withdrawing twice, or the leader withdrawing, has to fail
+                            let err = res.expect_err(&context);
+                            assert!(
+                                matches!(err, ContractError::NothingToWithdraw {}),
+                                "{}: {}",
+                                context,
+                                err
+                            );
+                        }
+                    }
+                }
+                Op::Close => {
+                    let res = contract.close_bidding(&mut app, &owner);
+                    if active {
+                        res.unwrap_or_else(|err| panic!("{}: {}", context, err));
+                        active = false;
+                        winner = leader;
+                        escrowed = winner.is_some();
+                    } else {
+                        assert!(res.is_err(), "{}: closed twice", context);
+                    }
+                }
+                Op::ConfirmDelivery { actor } => {
+                    let res = contract.confirm_delivery(&mut app, &actors[actor]);
+                    if escrowed && !settled && winner == Some(actor) {
+                        res.unwrap_or_else(|err| panic!("{}: {}", context, err));
+                        settled = true;
+                    } else {
+                        assert!(res.is_err(), "{}: delivery confirmed", context);
+                    }
+                }
+                Op::ReleaseEscrow => {
+                    app.update_block(|block| {
+                        block.time = block.time.plus_seconds(INVARIANT_DELIVERY_TIMEOUT)
+                    });
+
+                    let res = contract.release_escrow(&mut app, &owner);
+                    if escrowed && !settled {
+                        res.unwrap_or_else(|err| panic!("{}: {}", context, err));
+                        settled = true;
+                    } else {
+                        let err = res.expect_err(&context);
+                        assert!(
+                            matches!(err, ContractError::NoPendingEscrow {}),
+                            "{}: {}",
+                            context,
+                            err
+                        );
+                    }
+                }
+            }
+
+            // the contract holds exactly the bids that haven't been withdrawn or paid out
+            let mut held = 0;
+            for (actor, addr) in actors.iter().enumerate() {
+                let bid = contract.query_user_bid(&app, addr.to_string()).unwrap();
+                assert_eq!(bid.bid.amount.u128(), model[actor], "{}", context);
+                if !(settled && winner == Some(actor)) {
+                    held += model[actor];
+                }
+
+                let balance = app.wrap().query_balance(addr, "ubtc").unwrap();
+                assert_eq!(
+                    balance.amount.u128(),
+                    INVARIANT_BALANCE - model[actor],
+                    "{}",
+                    context
+                );
+            }
+            let contract_balance = app.wrap().query_balance(contract.addr(), "ubtc").unwrap();
+            assert_eq!(contract_balance.amount.u128(), held, "{}", context);
+
+            // once settled, the winning bid is split between the seller and the commission
+            let paid = if settled { highest } else { 0 };
+            let commission = paid * 5 / 100;
+            let owner_balance = app.wrap().query_balance(&owner, "ubtc").unwrap();
+            assert_eq!(
+                owner_balance.amount.u128(),
+                paid - commission,
+                "{}",
+                context
+            );
+            let treasury_balance = app.wrap().query_balance(&treasury, "ubtc").unwrap();
+            assert_eq!(treasury_balance.amount.u128(), commission, "{}", context);
+
+            // the leading bid never decreases, and the winner's bid stays escrowed
+            let status = contract.query_auction_status(&app).unwrap();
+            let status_highest = status
+                .highest_bid
+                .map(|bid| bid.bid.amount.u128())
+                .unwrap_or_default();
+            assert!(status_highest >= highest, "{}", context);
+            highest = status_highest;
+
+            if let Some(winner) = winner {
+                assert_eq!(model[winner], highest, "{}", context);
+            }
+
+            let escrow = contract.query_escrow(&app).unwrap();
+            assert_eq!(escrow.is_some(), escrowed, "{}", context);
+            if let Some(escrow) = escrow {
+                let status = if settled {
+                    EscrowStatus::Settled
+                } else {
+                    EscrowStatus::AwaitingDelivery
+                };
+                assert_eq!(escrow.status, status, "{}", context);
+            }
+        }
+    }
 }
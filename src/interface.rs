@@ -0,0 +1,235 @@
+//! Typed interface of the auction, enabled with the `interface` feature. It builds the messages
+//! and queries of the contract so callers don't have to write its JSON by hand. The messages are
+//! plain `CosmosMsg`s, so they can be returned from another contract or executed in
+//! `cw_multi_test::App`, and queries work with any `QuerierWrapper`, including `App::wrap`.
+
+use cosmwasm_std::{
+    Addr, Coin, CosmosMsg, Decimal, Empty, QuerierWrapper, QueryRequest, StdResult, Uint128,
+};
+
+use crate::helpers::CwAuctionContract;
+use crate::msg::{
+    AuctionStatusResponse, BidResponse, BidderStatusResponse, BlocklistResponse, ConfigResponse,
+    DeliveryEscrow, DepositsResponse, ExecuteMsg, HooksResponse, ItemMetadata, ItemResponse,
+    PauseScope, PriceSource, QueryMsg, SimulateBidResponse,
+};
+
+/// Auction deployed at `contract_addr`
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionInterface(CwAuctionContract);
+
+impl AuctionInterface {
+    pub fn new(contract_addr: impl Into<String>) -> Self {
+        Self(CwAuctionContract(Addr::unchecked(contract_addr)))
+    }
+
+    pub fn addr(&self) -> Addr {
+        self.0.addr()
+    }
+
+    /// Executes `msg` on the auction, sending `funds` along. Messages with many optional fields,
+    /// like `UpdateConfig` or bids with refund routes, are built with it directly.
+    pub fn execute(&self, msg: &ExecuteMsg, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        self.0.call(msg.clone(), funds)
+    }
+
+    pub fn query_request(&self, msg: &QueryMsg) -> StdResult<QueryRequest<Empty>> {
+        self.0.query_request(msg)
+    }
+
+    /// Bids `funds` for the sender
+    pub fn bid(&self, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        self.bid_for(None, None, funds)
+    }
+
+    /// Bids `funds` for `bidder`, with a merkle proof when the auction has an allowlist
+    pub fn bid_for(
+        &self,
+        bidder: Option<String>,
+        proof: Option<Vec<String>>,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        self.execute(
+            &ExecuteMsg::Bid {
+                bidder,
+                refund_address: None,
+                ibc_refund: None,
+                proof,
+            },
+            funds,
+        )
+    }
+
+    pub fn retract_funds(
+        &self,
+        withdraw_address: Option<String>,
+        amount: Option<Uint128>,
+    ) -> StdResult<CosmosMsg> {
+        self.execute(
+            &ExecuteMsg::RetractFunds {
+                withdraw_address,
+                amount,
+            },
+            vec![],
+        )
+    }
+
+    pub fn close_bidding(&self) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::CloseBidding {}, vec![])
+    }
+
+    pub fn process_refunds(&self, limit: Option<u32>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::ProcessRefunds { limit }, vec![])
+    }
+
+    pub fn update_merkle_root(&self, merkle_root: Option<String>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::UpdateMerkleRoot { merkle_root }, vec![])
+    }
+
+    pub fn add_to_blocklist(&self, addresses: Vec<String>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::AddToBlocklist { addresses }, vec![])
+    }
+
+    pub fn remove_from_blocklist(&self, addresses: Vec<String>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::RemoveFromBlocklist { addresses }, vec![])
+    }
+
+    pub fn pause(&self, scope: Option<PauseScope>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::Pause { scope }, vec![])
+    }
+
+    pub fn unpause(&self, scope: Option<PauseScope>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::Unpause { scope }, vec![])
+    }
+
+    pub fn update_guardian(&self, guardian: String) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::UpdateGuardian { guardian }, vec![])
+    }
+
+    pub fn update_price_source(&self, price_source: PriceSource) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::UpdatePriceSource { price_source }, vec![])
+    }
+
+    pub fn add_hook(&self, addr: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::AddHook { addr: addr.into() }, vec![])
+    }
+
+    pub fn remove_hook(&self, addr: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::RemoveHook { addr: addr.into() }, vec![])
+    }
+
+    pub fn update_item(&self, item: ItemMetadata) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::UpdateItem { item }, vec![])
+    }
+
+    pub fn confirm_delivery(&self) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::ConfirmDelivery {}, vec![])
+    }
+
+    pub fn release_escrow(&self) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::ReleaseEscrow {}, vec![])
+    }
+
+    pub fn open_dispute(&self) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::OpenDispute {}, vec![])
+    }
+
+    pub fn resolve_dispute(&self, buyer_share: Decimal) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::ResolveDispute { buyer_share }, vec![])
+    }
+
+    pub fn retry_payout(&self) -> StdResult<CosmosMsg> {
+        self.execute(&ExecuteMsg::RetryPayout {}, vec![])
+    }
+
+    pub fn allow_ibc_counterparty(
+        &self,
+        connection_id: String,
+        port_id: String,
+    ) -> StdResult<CosmosMsg> {
+        self.execute(
+            &ExecuteMsg::AllowIbcCounterparty {
+                connection_id,
+                port_id,
+            },
+            vec![],
+        )
+    }
+
+    pub fn remove_ibc_counterparty(
+        &self,
+        connection_id: String,
+        port_id: String,
+    ) -> StdResult<CosmosMsg> {
+        self.execute(
+            &ExecuteMsg::RemoveIbcCounterparty {
+                connection_id,
+                port_id,
+            },
+            vec![],
+        )
+    }
+
+    pub fn auction_status(&self, querier: &QuerierWrapper) -> StdResult<AuctionStatusResponse> {
+        self.0.status(querier)
+    }
+
+    pub fn config(&self, querier: &QuerierWrapper) -> StdResult<ConfigResponse> {
+        self.0.config(querier)
+    }
+
+    pub fn item(&self, querier: &QuerierWrapper) -> StdResult<ItemResponse> {
+        self.0.item(querier)
+    }
+
+    pub fn user_bid(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+    ) -> StdResult<BidResponse> {
+        self.0.user_bid(querier, bidder)
+    }
+
+    pub fn user_deposits(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+    ) -> StdResult<DepositsResponse> {
+        self.0.user_deposits(querier, bidder)
+    }
+
+    pub fn blocklist(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BlocklistResponse> {
+        self.0.blocklist(querier, start_after, limit)
+    }
+
+    pub fn hooks(&self, querier: &QuerierWrapper) -> StdResult<HooksResponse> {
+        self.0.hooks(querier)
+    }
+
+    pub fn simulate_bid(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+        amount: Vec<Coin>,
+        proof: Option<Vec<String>>,
+    ) -> StdResult<SimulateBidResponse> {
+        self.0.simulate_bid(querier, bidder, amount, proof)
+    }
+
+    pub fn bidder_status(
+        &self,
+        querier: &QuerierWrapper,
+        bidder: impl Into<String>,
+    ) -> StdResult<BidderStatusResponse> {
+        self.0.bidder_status(querier, bidder)
+    }
+
+    pub fn escrow(&self, querier: &QuerierWrapper) -> StdResult<Option<DeliveryEscrow>> {
+        self.0.escrow(querier)
+    }
+}
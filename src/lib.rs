@@ -1,8 +1,15 @@
 pub mod contract;
 mod error;
+pub mod events;
 pub mod helpers;
-pub mod integration_tests;
+pub mod ibc;
+pub mod ics20;
+#[cfg(test)]
+mod integration_tests;
+#[cfg(any(test, feature = "interface"))]
+pub mod interface;
 pub mod msg;
+#[cfg(test)]
 pub mod multitest;
 pub mod state;
 
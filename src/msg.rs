@@ -1,19 +1,212 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Coin, Decimal};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Coin, Decimal, StdResult, SubMsg, Timestamp, Uint128, WasmMsg,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub owner: Option<String>,
     pub auction_item_title: String,
+    /// Details of the item on sale besides its title
+    pub item: Option<ItemMetadata>,
+    /// Share of the seller's payout kept as commission. Defaults to 5%.
     pub commission_percentage: Option<Decimal>,
+    /// Receives the commission. Defaults to the instantiating address.
+    pub commission_recipient: Option<String>,
+    /// Hex encoded sha256 merkle root of the addresses allowed to bid.
+    /// When unset anyone can bid.
+    pub merkle_root: Option<String>,
+    /// Maximum cumulative bid a single bidder may place. Unlimited when unset.
+    pub max_bid_per_bidder: Option<Uint128>,
+    /// Address allowed to pause and unpause the auction. Defaults to the owner.
+    pub guardian: Option<String>,
+    /// Delegates escrowed bids to a validator while the auction runs.
+    pub staking: Option<StakingConfig>,
+    /// Refunds the previous highest bidder as soon as they are outbid. Defaults to false.
+    pub auto_refund_outbid: Option<bool>,
+    /// Seconds after their last bid before an outbid bidder may withdraw while bidding is open.
+    pub retract_cooldown: Option<u64>,
+    /// Share of each refund paid to whoever calls `ProcessRefunds`. Defaults to zero.
+    pub refund_keeper_tip: Option<Decimal>,
+    /// Denoms accepted for bids in addition to the bid denom. Requires a price source.
+    pub accepted_denoms: Option<Vec<String>>,
+    /// Converts accepted denoms into the bid denom
+    pub price_source: Option<PriceSource>,
+    /// Address that can resolve delivery disputes between the winner and the seller
+    pub arbiter: Option<String>,
+    /// Seconds after closing before the winning bid is released to the seller without the
+    /// winner confirming delivery. Defaults to 30 days.
+    pub delivery_timeout: Option<u64>,
 }
 
+#[cw_serde]
+#[derive(Default)]
+pub struct ItemMetadata {
+    pub description: Option<String>,
+    /// Link to an image or page describing the item
+    pub uri: Option<String>,
+    pub category: Option<String>,
+    pub attributes: Vec<ItemAttribute>,
+    /// Hex encoded sha256 of the off-chain item description, so it can't be swapped later
+    pub content_hash: Option<String>,
+}
+
+#[cw_serde]
+pub struct ItemAttribute {
+    pub key: String,
+    pub value: String,
+}
+
+/// ICS-20 route back to the chain an IBC bid came from
+#[cw_serde]
+pub struct IbcRefundRoute {
+    /// Transfer channel on this chain
+    pub channel_id: String,
+    /// Original sender on the counterparty chain
+    pub receiver: String,
+}
+
+#[cw_serde]
+pub enum PriceSource {
+    /// Owner set rates
+    Fixed { rates: Vec<DenomRate> },
+    /// Contract answering `OracleQueryMsg::Price`
+    Oracle { contract: String },
+}
+
+/// Amount of the bid denom one unit of `denom` is worth
+#[cw_serde]
+pub struct DenomRate {
+    pub denom: String,
+    pub rate: Decimal,
+}
+
+#[cw_serde]
+pub struct StakingConfig {
+    pub validator: String,
+}
+
+/// Part of the auction a guardian pause applies to
+#[cw_serde]
+pub enum PauseScope {
+    /// New bids, local and over IBC
+    Bidding,
+    /// Closing the auction and settling the escrow
+    Settlement,
+}
+
+/// Setting `UpdateConfig` can unset
+#[cw_serde]
+pub enum OptionalSetting {
+    MaxBidPerBidder,
+    RetractCooldown,
+    Arbiter,
+}
+
+/// Rewrites storage written by earlier versions, see `contract::migrate`
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub enum ExecuteMsg {
-    Bid {},
+    Bid {
+        /// Beneficiary credited with the bid. Defaults to the sender.
+        bidder: Option<String>,
+        /// Address refunds are sent to. Defaults to the bidder. Others can only set it with the
+        /// first funds of the bidder, after that only the bidder can change it.
+        refund_address: Option<String>,
+        /// Sends refunds back over IBC instead, for bids arriving through ICS-20 memo hooks.
+        /// Takes precedence over `refund_address`.
+        ibc_refund: Option<IbcRefundRoute>,
+        /// Hex encoded merkle proof for the bidder. Required when an allowlist is set.
+        proof: Option<Vec<String>>,
+    },
     // Withdraw { withdraw_address: Option<String> },
-    RetractFunds { withdraw_address: Option<String> },
+    /// Withdraws `amount`, or everything available when unset. Outbid bidders can
+    /// withdraw while bidding is open, the leader only after it closes.
+    RetractFunds {
+        withdraw_address: Option<String>,
+        amount: Option<Uint128>,
+    },
     CloseBidding {},
+    /// Refunds up to `limit` losing bidders once bidding has closed. Anyone can call it.
+    ProcessRefunds {
+        limit: Option<u32>,
+    },
+    UpdateMerkleRoot {
+        merkle_root: Option<String>,
+    },
+    AddToBlocklist {
+        addresses: Vec<String>,
+    },
+    RemoveFromBlocklist {
+        addresses: Vec<String>,
+    },
+    /// Stops bidding, settlement, or both when no scope is given, until unpaused. Funds can
+    /// still be retracted.
+    Pause {
+        scope: Option<PauseScope>,
+    },
+    Unpause {
+        scope: Option<PauseScope>,
+    },
+    /// Hands the pause rights to another address. Only the owner can call it, at any time.
+    UpdateGuardian {
+        guardian: String,
+    },
+    UpdatePriceSource {
+        price_source: PriceSource,
+    },
+    AddHook {
+        addr: String,
+    },
+    RemoveHook {
+        addr: String,
+    },
+    /// Changes the given settings, leaving unset ones as they are. Only the owner can call it,
+    /// and only while nobody else has bid.
+    UpdateConfig {
+        auction_item_title: Option<String>,
+        commission_percentage: Option<Decimal>,
+        commission_recipient: Option<String>,
+        max_bid_per_bidder: Option<Uint128>,
+        guardian: Option<String>,
+        retract_cooldown: Option<u64>,
+        refund_keeper_tip: Option<Decimal>,
+        arbiter: Option<String>,
+        delivery_timeout: Option<u64>,
+        /// Optional settings to unset, taking precedence over new values given for them
+        clear: Option<Vec<OptionalSetting>>,
+    },
+    /// Replaces the item metadata. Only the owner can call it, and only before the first bid.
+    UpdateItem {
+        item: ItemMetadata,
+    },
+    /// Releases the escrowed winning bid to the seller. Only the winner can call it.
+    ConfirmDelivery {},
+    /// Releases the escrowed winning bid to the seller once the delivery timeout has passed
+    /// without a dispute. Anyone can call it.
+    ReleaseEscrow {},
+    /// Holds the escrow until the arbiter resolves it. Callable by the winner or the seller.
+    OpenDispute {},
+    /// Pays `buyer_share` of the escrow back to the winner and the rest to the seller
+    ResolveDispute {
+        buyer_share: Decimal,
+    },
+    /// Sends the payout of a remote winner's escrow again after its packet failed. Anyone can
+    /// call it.
+    RetryPayout {},
+    /// Lets companion contracts behind `port_id` on the chain of `connection_id` open channels.
+    /// Only the owner can call it.
+    AllowIbcCounterparty {
+        connection_id: String,
+        port_id: String,
+    },
+    /// Stops new channels from the counterparty, channels already open stay open
+    RemoveIbcCounterparty {
+        connection_id: String,
+        port_id: String,
+    },
 }
 
 #[cw_serde]
@@ -23,8 +216,48 @@ pub enum QueryMsg {
     #[returns(AuctionStatusResponse)]
     GetAuctionStatus {},
 
+    /// Settings of the auction, without scanning the bids like `GetAuctionStatus`
+    #[returns(ConfigResponse)]
+    GetConfig {},
+
+    #[returns(ItemResponse)]
+    GetItem {},
+
+    /// Bid of `bidder`, either an address or `<channel_id>/<bidder>` for a bidder on the chain
+    /// of a companion contract
     #[returns(BidResponse)]
     GetUserBid { bidder: String },
+
+    /// Exact coins the bidder sent, as opposed to their normalized bid
+    #[returns(DepositsResponse)]
+    GetUserDeposits { bidder: String },
+
+    #[returns(BlocklistResponse)]
+    GetBlocklist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(HooksResponse)]
+    GetHooks {},
+
+    /// Winning bid held until delivery, unset before bidding closes
+    #[returns(Option<DeliveryEscrow>)]
+    GetEscrow {},
+
+    /// Checks a bid of the coins in `amount` by `bidder` like `Bid` would, without sending it.
+    /// `proof` is the bidder's merkle proof when the auction has an allowlist.
+    #[returns(SimulateBidResponse)]
+    SimulateBid {
+        bidder: String,
+        amount: Vec<Coin>,
+        proof: Option<Vec<String>>,
+    },
+
+    /// Standing of `bidder` in the auction and what they can withdraw. Remote bidders are given
+    /// like for `GetUserBid`.
+    #[returns(BidderStatusResponse)]
+    GetBidderStatus { bidder: String },
 }
 
 // We define a custom struct for each query response
@@ -32,14 +265,252 @@ pub enum QueryMsg {
 pub struct AuctionStatusResponse {
     pub owner: String,
     pub active: bool,
+    /// Bids are rejected while set
+    pub paused: bool,
+    /// Closing and escrow settlement are rejected while set
+    pub settlement_paused: bool,
     pub auction_item_title: String,
-    pub highest_bid: BidResponse,
+    /// Unset while nobody has bid
+    pub highest_bid: Option<BidResponse>,
     pub bidders_count: usize,
     pub commission_percentage: Decimal,
 }
 
+#[cw_serde]
+pub struct ConfigResponse {
+    /// Seller, receiving the winning bid
+    pub owner: String,
+    pub guardian: String,
+    pub arbiter: Option<String>,
+    pub auction_item_title: String,
+    /// Denom bids are valued and refunded partial withdrawals in
+    pub bid_denom: String,
+    /// Other denoms accepted for bids
+    pub accepted_denoms: Vec<String>,
+    pub price_source: Option<PriceSource>,
+    /// Share of the seller's payout sent to `commission_recipient`
+    pub commission_percentage: Decimal,
+    pub commission_recipient: String,
+    /// Share of each refund paid to whoever calls `ProcessRefunds`
+    pub refund_keeper_tip: Decimal,
+    pub max_bid_per_bidder: Option<Uint128>,
+    pub auto_refund_outbid: bool,
+    /// Seconds after their last bid before an outbid bidder may withdraw while bidding is open
+    pub retract_cooldown: Option<u64>,
+    /// Seconds the winning bid is escrowed for delivery after closing
+    pub delivery_timeout: u64,
+    /// Hex encoded merkle root of the bidder allowlist
+    pub merkle_root: Option<String>,
+    pub staking: Option<StakingConfig>,
+}
+
+#[cw_serde]
+pub struct ItemResponse {
+    pub title: String,
+    pub metadata: ItemMetadata,
+}
+
 #[cw_serde]
 pub struct BidResponse {
     pub bidder: String,
     pub bid: Coin,
 }
+
+#[cw_serde]
+pub struct SimulateBidResponse {
+    /// Whether the bid would be accepted
+    pub success: bool,
+    /// Bid of the bidder after adding `amount`, in the bid denom
+    pub new_total: Coin,
+    /// Whether the bidder would be leading afterwards
+    pub would_lead: bool,
+    /// Least amount of the bid denom the bidder has to add to take the lead
+    pub minimum_required: Coin,
+    /// Reason the bid would be rejected
+    pub error: Option<String>,
+}
+
+#[cw_serde]
+pub struct BidderStatusResponse {
+    pub bidder: String,
+    pub bid: Coin,
+    /// Position among all bidders starting at 1, unset without a bid
+    pub rank: Option<u32>,
+    pub leading: bool,
+    /// Least amount of the bid denom the bidder has to add to take the lead, zero while leading
+    pub amount_to_lead: Coin,
+    pub can_withdraw: bool,
+    /// What `RetractFunds` would pay out right now, including staking rewards
+    pub withdrawable: Vec<Coin>,
+    /// Coins owed to the bidder once they are outbid or bidding closed, whether or not they can
+    /// be withdrawn yet
+    pub pending_refunds: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct DepositsResponse {
+    pub bidder: String,
+    pub deposits: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct BlocklistResponse {
+    pub addresses: Vec<String>,
+}
+
+#[cw_serde]
+pub enum EscrowStatus {
+    AwaitingDelivery,
+    Disputed,
+    Settled,
+    /// The payout packet to a remote winner's companion failed, see `RetryPayout`
+    PayoutFailed,
+}
+
+/// Winning funds held after bidding closes until the item is delivered
+#[cw_serde]
+pub struct DeliveryEscrow {
+    pub winner: Addr,
+    pub bid: Coin,
+    /// Exact coins held for the seller, empty when a companion contract holds them
+    pub funds: Vec<Coin>,
+    /// When the funds can be released without the winner confirming delivery
+    pub release_at: Timestamp,
+    pub status: EscrowStatus,
+    /// Channel to the companion contract holding the funds of a remote winner
+    pub remote_channel: Option<String>,
+    /// Share of the funds paid back to the winner, set once settled
+    pub buyer_share: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct HooksResponse {
+    pub hooks: Vec<String>,
+}
+
+/// Events sent to every registered hook contract
+#[cw_serde]
+pub enum AuctionHookMsg {
+    NewBid {
+        bidder: String,
+        bid: Coin,
+    },
+    Outbid {
+        bidder: String,
+        bid: Coin,
+        outbid_by: String,
+    },
+    Closed {
+        highest_bid: Option<BidResponse>,
+    },
+    Settled {
+        winner: String,
+        amount: Coin,
+    },
+}
+
+/// Gas a single hook may use. Running out of gas can't be caught in `reply` without a limit, so a
+/// subscriber could otherwise burn the whole transaction.
+pub const HOOK_GAS_LIMIT: u64 = 300_000;
+
+impl AuctionHookMsg {
+    /// Wraps the hook in a submessage for `contract_addr`. Errors, including running out of its
+    /// `HOOK_GAS_LIMIT`, are caught in `reply` so a failing hook cannot block the auction.
+    pub fn into_sub_msg(self, contract_addr: impl Into<String>, id: u64) -> StdResult<SubMsg> {
+        let msg = to_binary(&AuctionHookExecuteMsg::AuctionHook(self))?;
+        Ok(SubMsg::reply_on_error(
+            WasmMsg::Execute {
+                contract_addr: contract_addr.into(),
+                msg,
+                funds: vec![],
+            },
+            id,
+        )
+        .with_gas_limit(HOOK_GAS_LIMIT))
+    }
+}
+
+/// Message hook contracts have to accept
+#[cw_serde]
+pub enum AuctionHookExecuteMsg {
+    AuctionHook(AuctionHookMsg),
+}
+
+/// Query interface price oracles have to implement
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum OracleQueryMsg {
+    #[returns(PriceResponse)]
+    Price { denom: String },
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    /// Amount of the bid denom one unit of `denom` is worth
+    pub rate: Decimal,
+}
+
+/// Packets of the auction IBC protocol. Companion contracts send `Bid`, escrowing the funds on
+/// their chain, and receive `Outbid`, `Settled` and `Payout` notifications in return.
+#[cw_serde]
+pub enum AuctionPacketMsg {
+    Bid {
+        /// Bidder on the counterparty chain
+        bidder: String,
+        /// Funds escrowed by the companion contract, valued like a local bid
+        amount: Coin,
+        /// Hex encoded merkle proof for the remote bidder. Required when an allowlist is set.
+        proof: Option<Vec<String>>,
+    },
+    /// The remote bidder was outbid, their escrow can be released
+    Outbid {
+        bidder: String,
+        bid: Coin,
+        outbid_by: String,
+    },
+    /// Bidding closed. `winner` is set when the highest bidder came through this channel,
+    /// every other escrow on the counterparty chain can be released. The winner's escrow is
+    /// held until `Payout`. `bid` is unset when nobody bid.
+    Settled {
+        winner: Option<String>,
+        bid: Option<Coin>,
+    },
+    /// The delivery escrow of the remote winner is settled. `buyer_share` of it goes back to
+    /// the winner. `commission` of the rest is transferred to `commission_recipient` and the
+    /// remainder to `seller`, both on this chain over ICS-20.
+    Payout {
+        winner: String,
+        seller: String,
+        buyer_share: Decimal,
+        commission: Decimal,
+        commission_recipient: String,
+    },
+}
+
+/// Acknowledgement written for every received packet
+#[cw_serde]
+pub enum AuctionPacketAck {
+    Result(Binary),
+    Error(String),
+}
+
+/// Callbacks of the chain, see `contract::sudo`
+#[cw_serde]
+pub enum SudoMsg {
+    /// Sent by the ibc-hooks middleware once a transfer with an `ibc_callback` memo completed
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
+
+#[cw_serde]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
+}
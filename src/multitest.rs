@@ -1,21 +1,34 @@
-use cosmwasm_std::{Addr, Coin, StdResult};
+use cosmwasm_std::{Addr, Coin, Decimal, StdResult};
 use cw_multi_test::{App, ContractWrapper, Executor};
 
 use crate::{
-    contract::{execute, instantiate, query},
-    msg::{AuctionStatusResponse, BidResponse, ExecuteMsg, InstantiateMsg, QueryMsg},
+    contract::{execute, instantiate, migrate, query, reply},
+    msg::{
+        AuctionStatusResponse, BidResponse, BidderStatusResponse, BlocklistResponse,
+        ConfigResponse, DeliveryEscrow, DepositsResponse, ExecuteMsg, HooksResponse,
+        InstantiateMsg, ItemMetadata, ItemResponse, MigrateMsg, PauseScope, PriceSource, QueryMsg,
+        SimulateBidResponse,
+    },
     ContractError,
 };
 
 pub struct AuctionContract(Addr);
 
+impl From<Addr> for AuctionContract {
+    fn from(addr: Addr) -> Self {
+        AuctionContract(addr)
+    }
+}
+
 impl AuctionContract {
     pub fn addr(&self) -> &Addr {
         &self.0
     }
 
     pub fn store_code(app: &mut App) -> u64 {
-        let contract = ContractWrapper::new(execute, instantiate, query);
+        let contract = ContractWrapper::new(execute, instantiate, query)
+            .with_reply(reply)
+            .with_migrate(migrate);
         app.store_code(Box::new(contract))
     }
 
@@ -43,18 +56,97 @@ impl AuctionContract {
         .map(AuctionContract)
     }
 
+    #[track_caller]
+    pub fn migrate(&self, app: &mut App, sender: &Addr, code_id: u64) -> Result<(), ContractError> {
+        app.migrate_contract(sender.clone(), self.0.clone(), &MigrateMsg {}, code_id)
+            .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
     #[track_caller]
     pub fn query_auction_status(&self, app: &App) -> StdResult<AuctionStatusResponse> {
         app.wrap()
             .query_wasm_smart(self.0.clone(), &QueryMsg::GetAuctionStatus {})
     }
 
+    #[track_caller]
+    pub fn query_config(&self, app: &App) -> StdResult<ConfigResponse> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::GetConfig {})
+    }
+
+    #[track_caller]
+    pub fn query_item(&self, app: &App) -> StdResult<ItemResponse> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::GetItem {})
+    }
+
     #[track_caller]
     pub fn query_user_bid(&self, app: &App, bidder: String) -> StdResult<BidResponse> {
         app.wrap()
             .query_wasm_smart(self.0.clone(), &QueryMsg::GetUserBid { bidder })
     }
 
+    #[track_caller]
+    pub fn query_simulate_bid(
+        &self,
+        app: &App,
+        bidder: String,
+        amount: Vec<Coin>,
+        proof: Option<Vec<String>>,
+    ) -> StdResult<SimulateBidResponse> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::SimulateBid {
+                bidder,
+                amount,
+                proof,
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_bidder_status(
+        &self,
+        app: &App,
+        bidder: String,
+    ) -> StdResult<BidderStatusResponse> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::GetBidderStatus { bidder })
+    }
+
+    #[track_caller]
+    pub fn query_user_deposits(&self, app: &App, bidder: String) -> StdResult<DepositsResponse> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::GetUserDeposits { bidder })
+    }
+
+    #[track_caller]
+    pub fn query_blocklist(
+        &self,
+        app: &App,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BlocklistResponse> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::GetBlocklist { start_after, limit },
+        )
+    }
+
+    #[track_caller]
+    pub fn query_hooks(&self, app: &App) -> StdResult<HooksResponse> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::GetHooks {})
+    }
+
+    #[track_caller]
+    pub fn query_escrow(&self, app: &App) -> StdResult<Option<DeliveryEscrow>> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::GetEscrow {})
+    }
+
     #[track_caller]
     pub fn bid(
         &self,
@@ -65,8 +157,62 @@ impl AuctionContract {
         app.execute_contract(
             sender.clone(),
             self.0.clone(),
-            &ExecuteMsg::Bid {},
-            &bid_funds,
+            &ExecuteMsg::Bid {
+                bidder: None,
+                refund_address: None,
+                ibc_refund: None,
+                proof: None,
+            },
+            bid_funds,
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn bid_with_proof(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        bid_funds: &[Coin],
+        proof: Vec<String>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::Bid {
+                bidder: None,
+                refund_address: None,
+                ibc_refund: None,
+                proof: Some(proof),
+            },
+            bid_funds,
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn bid_on_behalf(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        bidder: &Addr,
+        refund_address: Option<String>,
+        bid_funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::Bid {
+                bidder: Some(bidder.to_string()),
+                refund_address,
+                ibc_refund: None,
+                proof: None,
+            },
+            bid_funds,
         )
         .map_err(|err| err.downcast::<ContractError>().unwrap())?;
 
@@ -83,7 +229,31 @@ impl AuctionContract {
         app.execute_contract(
             sender.clone(),
             self.0.clone(),
-            &ExecuteMsg::RetractFunds { withdraw_address },
+            &ExecuteMsg::RetractFunds {
+                withdraw_address,
+                amount: None,
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn retract_amount(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        amount: u128,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::RetractFunds {
+                withdraw_address: None,
+                amount: Some(amount.into()),
+            },
             &[],
         )
         .map_err(|err| err.downcast::<ContractError>().unwrap())?;
@@ -103,4 +273,260 @@ impl AuctionContract {
 
         Ok(())
     }
+
+    #[track_caller]
+    pub fn update_merkle_root(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        merkle_root: Option<String>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::UpdateMerkleRoot { merkle_root },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn add_to_blocklist(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addresses: Vec<String>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::AddToBlocklist { addresses },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn remove_from_blocklist(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addresses: Vec<String>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::RemoveFromBlocklist { addresses },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn pause(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        scope: Option<PauseScope>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::Pause { scope },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn unpause(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        scope: Option<PauseScope>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::Unpause { scope },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn update_guardian(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        guardian: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::UpdateGuardian {
+                guardian: guardian.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn add_hook(&self, app: &mut App, sender: &Addr, addr: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::AddHook {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn remove_hook(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        addr: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::RemoveHook {
+                addr: addr.to_string(),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn process_refunds(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        limit: Option<u32>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::ProcessRefunds { limit },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn update_price_source(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        price_source: PriceSource,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::UpdatePriceSource { price_source },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn update_item(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        item: ItemMetadata,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::UpdateItem { item },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn confirm_delivery(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::ConfirmDelivery {},
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn release_escrow(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::ReleaseEscrow {},
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn open_dispute(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::OpenDispute {},
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    #[track_caller]
+    pub fn resolve_dispute(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        buyer_share: Decimal,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecuteMsg::ResolveDispute { buyer_share },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
 }
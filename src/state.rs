@@ -1,9 +1,95 @@
-use cosmwasm_std::{Coin, Decimal};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw_storage_plus::{Deque, Item, Map};
 
-pub const OWNER: Item<String> = Item::new("owner");
+use crate::msg::{DeliveryEscrow, IbcRefundRoute, ItemMetadata, PriceSource, StakingConfig};
+
+/// Accumulated bid amount × seconds a bidder's funds have been delegated.
+#[cw_serde]
+pub struct StakeWeight {
+    pub weight: Uint128,
+    pub last_updated: Timestamp,
+}
+
+/// Snapshot of the staking rewards taken when bidding closes.
+#[cw_serde]
+pub struct StakingRewards {
+    pub total_rewards: Uint128,
+    pub total_weight: Uint128,
+    pub closed_at: Timestamp,
+    /// Sum of the staked bids
+    pub total_staked: Uint128,
+    /// Stake undelegated at close, less than `total_staked` after a slash
+    pub undelegated: Uint128,
+    /// Liquid balance right after undelegating. The stake is back once it grew by `undelegated`.
+    pub balance_at_close: Uint128,
+    pub unbonded: bool,
+}
+
+/// ICS-20 refund waiting for the outcome of its transfer
+#[cw_serde]
+pub struct PendingIbcRefund {
+    pub bidder: Addr,
+    pub channel_id: String,
+    pub amount: Coin,
+}
+
+/// Bidder that placed their bid through an IBC channel
+#[cw_serde]
+pub struct RemoteBidder {
+    pub channel_id: String,
+    pub bidder: String,
+}
+
+pub const OWNER: Item<Addr> = Item::new("owner");
+pub const GUARDIAN: Item<Addr> = Item::new("guardian");
 pub const AUCTION_ITEM_TITLE: Item<String> = Item::new("auction_item_title");
+pub const ITEM_METADATA: Item<ItemMetadata> = Item::new("item_metadata");
 pub const COMMISSION_PERCENTAGE: Item<Decimal> = Item::new("commission_percentage");
+pub const COMMISSION_RECIPIENT: Item<Addr> = Item::new("commission_recipient");
 pub const ACTIVE: Item<bool> = Item::new("active");
+/// Bidding pause, see `SETTLEMENT_PAUSED` for closing and escrow settlement
+pub const PAUSED: Item<bool> = Item::new("paused");
+pub const SETTLEMENT_PAUSED: Item<bool> = Item::new("settlement_paused");
+pub const AUTO_REFUND_OUTBID: Item<bool> = Item::new("auto_refund_outbid");
+pub const RETRACT_COOLDOWN: Item<u64> = Item::new("retract_cooldown");
+pub const REFUND_KEEPER_TIP: Item<Decimal> = Item::new("refund_keeper_tip");
+/// Last bidder handled by `ProcessRefunds`
+pub const REFUND_CURSOR: Item<Addr> = Item::new("refund_cursor");
+pub const MERKLE_ROOT: Item<String> = Item::new("merkle_root");
+pub const MAX_BID_PER_BIDDER: Item<Uint128> = Item::new("max_bid_per_bidder");
+/// Denoms accepted for bids besides the reference bid denom
+pub const ACCEPTED_DENOMS: Item<Vec<String>> = Item::new("accepted_denoms");
+pub const PRICE_SOURCE: Item<PriceSource> = Item::new("price_source");
+pub const STAKING_CONFIG: Item<StakingConfig> = Item::new("staking_config");
+/// Rewards the distribution module paid out while bidding, every delegation withdraws them
+pub const COLLECTED_REWARDS: Item<Uint128> = Item::new("collected_rewards");
+/// Liquid balance expected after the pending staking message, anything above it is rewards
+pub const STAKING_EXPECTED_BALANCE: Item<Uint128> = Item::new("staking_expected_balance");
+pub const STAKING_REWARDS: Item<StakingRewards> = Item::new("staking_rewards");
+pub const ARBITER: Item<Addr> = Item::new("arbiter");
+pub const DELIVERY_TIMEOUT: Item<u64> = Item::new("delivery_timeout");
+pub const ESCROW: Item<DeliveryEscrow> = Item::new("escrow");
 
-pub const BIDS: Map<String, Coin> = Map::new("bids");
+pub const BIDS: Map<&Addr, Coin> = Map::new("bids");
+/// Exact coins each bidder sent, keyed by (bidder, denom)
+pub const DEPOSITS: Map<(&Addr, &str), Uint128> = Map::new("deposits");
+pub const BLOCKLIST: Map<&Addr, Empty> = Map::new("blocklist");
+pub const STAKE_WEIGHTS: Map<&Addr, StakeWeight> = Map::new("stake_weights");
+pub const HOOKS: Map<&Addr, Empty> = Map::new("hooks");
+pub const LAST_BID_AT: Map<&Addr, Timestamp> = Map::new("last_bid_at");
+pub const REFUND_ADDRESSES: Map<&Addr, Addr> = Map::new("refund_addresses");
+pub const IBC_REFUND_ROUTES: Map<&Addr, IbcRefundRoute> = Map::new("ibc_refund_routes");
+/// Connections and counterparty ports companion contracts may open channels from
+pub const IBC_COUNTERPARTIES: Map<(&str, &str), Empty> = Map::new("ibc_counterparties");
+/// Open channels to companion contracts
+pub const IBC_CHANNELS: Map<String, Empty> = Map::new("ibc_channels");
+/// Bidders whose funds are escrowed on another chain, keyed by their bid key
+pub const REMOTE_BIDDERS: Map<&Addr, RemoteBidder> = Map::new("remote_bidders");
+/// IBC refunds sent in the current transaction, in the order their submessages reply
+pub const IBC_REFUNDS_SENDING: Deque<PendingIbcRefund> = Deque::new("ibc_refunds_sending");
+/// IBC refunds by channel and packet sequence until ibc-hooks reports how their transfer ended
+pub const IBC_REFUNDS_IN_FLIGHT: Map<(&str, u64), PendingIbcRefund> =
+    Map::new("ibc_refunds_in_flight");
+/// Refunds whose transfer failed, paid out again by `RetractFunds`
+pub const FAILED_REFUNDS: Map<&Addr, Vec<Coin>> = Map::new("failed_refunds");